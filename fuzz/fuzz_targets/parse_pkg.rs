@@ -0,0 +1,12 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use repkg::pkg::Pkg;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(pkg) = Pkg::parse(data.to_vec()) {
+        for entry in &pkg.entries {
+            assert_eq!(pkg.entry_data(entry).unwrap().len(), entry.length as usize);
+        }
+    }
+});
@@ -0,0 +1,20 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use repkg::tex::{MipLevel, Tex};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(tex) = Tex::parse(data) {
+        for image in &tex.images {
+            for mipmap in &image.mipmaps {
+                if let Ok(decoded) = tex.decode_mipmap(mipmap) {
+                    let _ = tex.crop_padding(decoded);
+                }
+            }
+        }
+        let _ = tex.select_mipmaps(MipLevel::All);
+        if tex.frames.is_some() {
+            let _ = repkg::anim::frames(&tex);
+        }
+    }
+});
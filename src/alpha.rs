@@ -0,0 +1,67 @@
+use crate::tex;
+use image::RgbaImage;
+use std::borrow::Cow;
+
+// 导出图片的透明通道处理方式
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlphaMode {
+    #[default]
+    Keep, // 保留透明通道
+    Matte([u8; 3]), // 按透明度混合到指定底色上，输出不透明图片
+    Unpremultiply,  // 颜色除以透明度，还原预乘透明度的纹理
+}
+
+impl AlphaMode {
+    // 与界面下拉框序号对应：0 保留; 1 填充底色; 2 反预乘
+    pub fn from_status(status: usize, matte: [u8; 3]) -> AlphaMode {
+        match status {
+            1 => AlphaMode::Matte(matte),
+            2 => AlphaMode::Unpremultiply,
+            _ => AlphaMode::Keep,
+        }
+    }
+}
+
+pub fn apply(image: &mut RgbaImage, mode: AlphaMode) {
+    match mode {
+        AlphaMode::Keep => {}
+        AlphaMode::Matte(matte) => {
+            for pixel in image.pixels_mut() {
+                let alpha = pixel[3] as u32;
+                for (channel, background) in pixel.0[..3].iter_mut().zip(matte) {
+                    *channel = ((*channel as u32 * alpha + background as u32 * (255 - alpha) + 127)
+                        / 255) as u8;
+                }
+                pixel[3] = 255;
+            }
+        }
+        AlphaMode::Unpremultiply => {
+            for pixel in image.pixels_mut() {
+                let alpha = pixel[3] as u32;
+                if alpha == 0 || alpha == 255 {
+                    continue;
+                }
+                for channel in &mut pixel.0[..3] {
+                    *channel = ((*channel as u32 * 255 + alpha / 2) / alpha).min(255) as u8;
+                }
+            }
+        }
+    }
+}
+
+// 对直接输出的 png 文件（pkg 中的图片、内嵌 png 纹理）做同样的处理，
+// 其他格式（jpg 没有透明通道，gif/webp 可能是动图）原样返回
+pub fn apply_to_file<'a>(
+    name: &str,
+    data: &'a [u8],
+    mode: AlphaMode,
+) -> Result<Cow<'a, [u8]>, String> {
+    if mode == AlphaMode::Keep || !name.to_ascii_lowercase().ends_with(".png") {
+        return Ok(Cow::Borrowed(data));
+    }
+    let mut image = tex::decode_embedded(data)
+        .map_err(|e| format!("解码 {} 失败: {}", name, e))?
+        .to_rgba8();
+    apply(&mut image, mode);
+    Ok(Cow::Owned(tex::encode_png(&image)?))
+}
@@ -0,0 +1,281 @@
+use crate::tex::{self, Tex, TexFrame};
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, RgbaImage};
+use serde_json::json;
+
+// 动图纹理的导出格式
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimFormat {
+    #[default]
+    Sheet, // 保持精灵图，不合成动图
+    Gif,
+    Apng,
+    WebP,
+    Frames, // 逐帧 png + 帧清单
+}
+
+impl AnimFormat {
+    // 与界面下拉框序号对应：0 精灵图; 1 GIF; 2 APNG; 3 WebP; 4 帧序列
+    pub fn from_status(status: usize) -> AnimFormat {
+        match status {
+            1 => AnimFormat::Gif,
+            2 => AnimFormat::Apng,
+            3 => AnimFormat::WebP,
+            4 => AnimFormat::Frames,
+            _ => AnimFormat::Sheet,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            AnimFormat::Sheet | AnimFormat::Apng => "png",
+            AnimFormat::Gif => "gif",
+            AnimFormat::WebP => "webp",
+            AnimFormat::Frames => "frames.json",
+        }
+    }
+}
+
+pub struct AnimFrame {
+    pub image: RgbaImage,
+    pub delay_ms: u32, // 帧时长（毫秒）
+}
+
+// 所有帧合计占用内存的上限
+const MAX_TOTAL_BYTES: u64 = 1024 * 1024 * 1024;
+
+// 按 TEXS 帧表从精灵图中切出各帧，所有帧均为动图画布尺寸
+pub fn frames(tex: &Tex) -> Result<Vec<AnimFrame>, String> {
+    let info = tex
+        .frames
+        .as_ref()
+        .ok_or_else(|| "tex 不是动图".to_string())?;
+    if info.width == 0 || info.height == 0 || info.frames.is_empty() {
+        return Err("动图帧信息为空".to_string());
+    }
+    let frame_bytes = tex::check_dimensions(info.width, info.height)? as u64;
+    let total = frame_bytes * info.frames.len() as u64;
+    if total > MAX_TOTAL_BYTES {
+        return Err(format!(
+            "动图共 {} 帧，需要 {} 字节内存，超过上限 {} 字节",
+            info.frames.len(),
+            total,
+            MAX_TOTAL_BYTES
+        ));
+    }
+
+    // 每张精灵图只解码一次
+    let mut sheets: Vec<Option<RgbaImage>> = vec![None; tex.images.len()];
+    let mut result = Vec::new();
+    for frame in &info.frames {
+        let index = frame.image_id as usize;
+        let image = tex
+            .images
+            .get(index)
+            .ok_or_else(|| format!("帧引用了不存在的图片 {}", index))?;
+        if sheets[index].is_none() {
+            let mipmap = image
+                .mipmaps
+                .first()
+                .ok_or_else(|| format!("图片 {} 没有数据", index))?;
+            sheets[index] = Some(tex.decode_mipmap(mipmap)?);
+        }
+        let sheet = sheets[index].as_ref().unwrap();
+        result.push(AnimFrame {
+            image: sample_frame(sheet, frame, info.width, info.height),
+            delay_ms: ((frame.frametime * 1000.0).round() as u32).max(10),
+        });
+    }
+    Ok(result)
+}
+
+// 帧的 x 轴为 (width, width_y)，y 轴为 (height_x, height)，
+// 按这两个轴向量采样即可同时处理普通、翻转与旋转存放的帧
+fn sample_frame(sheet: &RgbaImage, frame: &TexFrame, width: u32, height: u32) -> RgbaImage {
+    let frame_w = frame.width.hypot(frame.width_y);
+    let frame_h = frame.height_x.hypot(frame.height);
+    let mut canvas = RgbaImage::new(width, height);
+    if frame_w < 1.0 || frame_h < 1.0 {
+        return canvas;
+    }
+
+    let (ax, ay) = (frame.width / frame_w, frame.width_y / frame_w);
+    let (bx, by) = (frame.height_x / frame_h, frame.height / frame_h);
+    let out_w = (frame_w.round() as u32).min(width);
+    let out_h = (frame_h.round() as u32).min(height);
+    for v in 0..out_h {
+        for u in 0..out_w {
+            let fu = u as f32 + 0.5;
+            let fv = v as f32 + 0.5;
+            let sx = (frame.x + fu * ax + fv * bx).floor();
+            let sy = (frame.y + fu * ay + fv * by).floor();
+            if sx < 0.0 || sy < 0.0 {
+                continue;
+            }
+            let (sx, sy) = (sx as u32, sy as u32);
+            if sx < sheet.width() && sy < sheet.height() {
+                canvas.put_pixel(u, v, *sheet.get_pixel(sx, sy));
+            }
+        }
+    }
+    canvas
+}
+
+// 将各帧编码为动图文件内容
+pub fn encode(frames: &[AnimFrame], format: AnimFormat) -> Result<Vec<u8>, String> {
+    if frames.is_empty() {
+        return Err("没有可保存的帧".to_string());
+    }
+    match format {
+        AnimFormat::Gif => encode_gif(frames),
+        AnimFormat::Apng => encode_apng(frames),
+        AnimFormat::WebP => encode_webp(frames),
+        AnimFormat::Sheet | AnimFormat::Frames => Err(format!("{:?} 不是动图格式", format)),
+    }
+    .map_err(|e| format!("编码动图失败: {}", e))
+}
+
+// 帧序列：每帧为 <stem>_000.png 等编号文件，<stem>.frames.json 记录帧矩形与时长
+// 返回 (文件名, 内容) 列表
+pub fn sequence(
+    tex: &Tex,
+    frames: &[AnimFrame],
+    stem: &str,
+) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let info = tex
+        .frames
+        .as_ref()
+        .ok_or_else(|| "tex 不是动图".to_string())?;
+
+    let mut files = Vec::new();
+    let mut entries = Vec::new();
+    for (index, (frame, info)) in frames.iter().zip(&info.frames).enumerate() {
+        let file = format!("{}_{:03}.png", stem, index);
+        let data =
+            tex::encode_png(&frame.image).map_err(|e| format!("编码帧 {} 失败: {}", file, e))?;
+        entries.push(json!({
+            "file": file,
+            "image": info.image_id,
+            "x": info.x,
+            "y": info.y,
+            "width": info.width,
+            "width_y": info.width_y,
+            "height_x": info.height_x,
+            "height": info.height,
+            "duration_ms": frame.delay_ms,
+        }));
+        files.push((file, data));
+    }
+
+    let content = json!({
+        "width": info.width,
+        "height": info.height,
+        "frames": entries,
+    });
+    let text = serde_json::to_string_pretty(&content).map_err(|e| e.to_string())?;
+    files.push((format!("{}.frames.json", stem), text.into_bytes()));
+    Ok(files)
+}
+
+fn encode_gif(frames: &[AnimFrame]) -> Result<Vec<u8>, String> {
+    let mut data = Vec::new();
+    {
+        let mut encoder = GifEncoder::new(&mut data);
+        encoder
+            .set_repeat(Repeat::Infinite)
+            .map_err(|e| e.to_string())?;
+        encoder
+            .encode_frames(frames.iter().map(|frame| {
+                Frame::from_parts(
+                    frame.image.clone(),
+                    0,
+                    0,
+                    Delay::from_numer_denom_ms(frame.delay_ms, 1),
+                )
+            }))
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(data)
+}
+
+fn encode_apng(frames: &[AnimFrame]) -> Result<Vec<u8>, String> {
+    let (width, height) = frames[0].image.dimensions();
+    let mut data = Vec::new();
+    let mut encoder = png::Encoder::new(&mut data, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .set_animated(frames.len() as u32, 0)
+        .map_err(|e| e.to_string())?;
+    let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+    for frame in frames {
+        let delay = frame.delay_ms.min(u16::MAX as u32) as u16;
+        writer
+            .set_frame_delay(delay, 1000)
+            .map_err(|e| e.to_string())?;
+        writer
+            .write_image_data(frame.image.as_raw())
+            .map_err(|e| e.to_string())?;
+    }
+    writer.finish().map_err(|e| e.to_string())?;
+    Ok(data)
+}
+
+// 动画 WebP：RIFF/WEBP + VP8X + ANIM + 每帧一个包含无损 VP8L 数据的 ANMF
+fn encode_webp(frames: &[AnimFrame]) -> Result<Vec<u8>, String> {
+    let (width, height) = frames[0].image.dimensions();
+    let mut body = Vec::new();
+
+    let mut vp8x = vec![0x12, 0, 0, 0]; // 动画 + alpha 标志
+    vp8x.extend_from_slice(&(width - 1).to_le_bytes()[..3]);
+    vp8x.extend_from_slice(&(height - 1).to_le_bytes()[..3]);
+    write_chunk(&mut body, b"VP8X", &vp8x);
+
+    let mut anim = vec![0u8; 4]; // 背景色
+    anim.extend_from_slice(&0u16.to_le_bytes()); // 无限循环
+    write_chunk(&mut body, b"ANIM", &anim);
+
+    for frame in frames {
+        let mut encoded = Vec::new();
+        image_webp::WebPEncoder::new(&mut encoded)
+            .encode(
+                frame.image.as_raw(),
+                width,
+                height,
+                image_webp::ColorType::Rgba8,
+            )
+            .map_err(|e| e.to_string())?;
+
+        let mut anmf = Vec::new();
+        anmf.extend_from_slice(&[0; 6]); // 帧偏移 x/2、y/2
+        anmf.extend_from_slice(&(width - 1).to_le_bytes()[..3]);
+        anmf.extend_from_slice(&(height - 1).to_le_bytes()[..3]);
+        anmf.extend_from_slice(&frame.delay_ms.min(0xff_ffff).to_le_bytes()[..3]);
+        // 不与上一帧混合
+        anmf.push(0x02);
+        // 跳过简单格式的 RIFF/WEBP 头部，保留 VP8L 块；编码器输出其他结构时报错，不拼出损坏的文件
+        match encoded.get(12..16) {
+            Some(b"VP8L") if encoded.starts_with(b"RIFF") && &encoded[8..12] == b"WEBP" => {
+                anmf.extend_from_slice(&encoded[12..])
+            }
+            _ => return Err("WebP 编码结果不是单个 VP8L 块".to_string()),
+        }
+        write_chunk(&mut body, b"ANMF", &anmf);
+    }
+
+    let mut data = Vec::with_capacity(body.len() + 12);
+    data.extend_from_slice(b"RIFF");
+    data.extend_from_slice(&(body.len() as u32 + 4).to_le_bytes());
+    data.extend_from_slice(b"WEBP");
+    data.extend_from_slice(&body);
+    Ok(data)
+}
+
+fn write_chunk(out: &mut Vec<u8>, name: &[u8; 4], payload: &[u8]) {
+    out.extend_from_slice(name);
+    out.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    out.extend_from_slice(payload);
+    if payload.len() % 2 == 1 {
+        out.push(0);
+    }
+}
@@ -0,0 +1,177 @@
+use crate::source::Package;
+use crate::tex::{Tex, TexFormat};
+use serde_json::Value;
+use std::collections::HashMap;
+
+// 纹理用途：作品图片，或遮罩、法线、流动、噪声等只供着色器使用的辅助纹理
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextureRole {
+    Artwork,
+    Auxiliary,
+}
+
+impl TextureRole {
+    pub fn name(self) -> &'static str {
+        match self {
+            TextureRole::Artwork => "作品",
+            TextureRole::Auxiliary => "辅助",
+        }
+    }
+}
+
+// 文件名中出现这些词时视为辅助纹理
+const AUXILIARY_WORDS: [&str; 9] = [
+    "mask", "normal", "nrm", "noise", "flow", "flowmap", "opacity", "util", "gradient",
+];
+
+// 法线贴图检测只解码不超过该边长的最小一级 mipmap
+const NORMAL_SAMPLE_SIZE: u32 = 256;
+
+// 先根据材质与特效 json 中的纹理引用分类：材质第一个纹理为作品，其余纹理以及特效使用的纹理为辅助；
+// 未被引用的纹理再按文件名、像素格式和内容（法线贴图）判断
+#[derive(Default)]
+pub struct Classifier {
+    references: HashMap<String, TextureRole>,
+}
+
+impl Classifier {
+    pub fn new() -> Classifier {
+        Classifier::default()
+    }
+
+    // 读取同一壁纸所有 pkg 中的 json 条目，引用可能跨 pkg
+    pub fn from_packages(packages: &[Package]) -> Classifier {
+        let mut classifier = Classifier::new();
+        for package in packages {
+            for (index, entry) in package.entries.iter().enumerate() {
+                if entry.name.ends_with(".json") {
+                    if let Ok(data) = package.read(index) {
+                        classifier.add_json(&entry.name, &data);
+                    }
+                }
+            }
+        }
+        classifier
+    }
+
+    // 读取 pkg 中的 json 条目（scene.json、materials/*.json、effects/*/effect.json）
+    pub fn add_json(&mut self, name: &str, data: &[u8]) {
+        let json: Value = match serde_json::from_slice(data) {
+            Ok(json) => json,
+            Err(_) => return,
+        };
+        let name = name.replace('\\', "/");
+        // 特效材质与场景中特效的 passes 只引用遮罩、噪声等辅助纹理
+        let effect = name.starts_with("materials/effects/")
+            || name.starts_with("effects/")
+            || name == "scene.json";
+        self.collect(&json, effect);
+    }
+
+    fn collect(&mut self, value: &Value, effect: bool) {
+        match value {
+            Value::Object(map) => {
+                if let Some(Value::Array(passes)) = map.get("passes") {
+                    for pass in passes {
+                        if let Some(Value::Array(textures)) = pass.get("textures") {
+                            for (slot, texture) in textures.iter().enumerate() {
+                                if let Some(texture) = texture.as_str() {
+                                    let role = if slot == 0 && !effect {
+                                        TextureRole::Artwork
+                                    } else {
+                                        TextureRole::Auxiliary
+                                    };
+                                    self.add_reference(texture, role);
+                                }
+                            }
+                        }
+                    }
+                }
+                for child in map.values() {
+                    self.collect(child, effect);
+                }
+            }
+            Value::Array(items) => {
+                for item in items {
+                    self.collect(item, effect);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // 同一纹理既作为作品又作为辅助引用时按作品处理
+    fn add_reference(&mut self, texture: &str, role: TextureRole) {
+        let key = reference_key(texture);
+        let entry = self.references.entry(key).or_insert(role);
+        if role == TextureRole::Artwork {
+            *entry = role;
+        }
+    }
+
+    // 返回分类与依据，name 为 pkg 中的条目路径
+    pub fn classify(&self, name: &str, tex: &Tex) -> (TextureRole, &'static str) {
+        match self.references.get(&reference_key(name)) {
+            Some(TextureRole::Artwork) => return (TextureRole::Artwork, "材质主纹理"),
+            Some(TextureRole::Auxiliary) => {
+                return (TextureRole::Auxiliary, "材质或特效的辅助纹理")
+            }
+            None => {}
+        }
+        if has_auxiliary_word(name) {
+            return (TextureRole::Auxiliary, "文件名");
+        }
+        if matches!(tex.header.format, TexFormat::R8 | TexFormat::Rg88) {
+            return (TextureRole::Auxiliary, "单通道格式");
+        }
+        if looks_like_normal_map(tex) {
+            return (TextureRole::Auxiliary, "法线贴图");
+        }
+        (TextureRole::Artwork, "默认")
+    }
+}
+
+// 纹理引用写作 materials/ 下不带扩展名的相对路径，如 "bg"、"masks/water"
+fn reference_key(name: &str) -> String {
+    let name = name.replace('\\', "/").to_ascii_lowercase();
+    let name = name.trim_start_matches("materials/");
+    name.strip_suffix(".tex").unwrap_or(name).to_string()
+}
+
+fn has_auxiliary_word(name: &str) -> bool {
+    name.to_ascii_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .any(|word| {
+            AUXILIARY_WORDS.contains(&word)
+                || word.ends_with("mask")
+                || word.ends_with("normal")
+                || word.ends_with("noise")
+        })
+}
+
+// 法线贴图绝大部分像素偏蓝紫：R、G 在 128 附近，B 接近 255
+fn looks_like_normal_map(tex: &Tex) -> bool {
+    if tex.is_embedded() || tex.is_gif() {
+        return false;
+    }
+    let mipmap = match tex.images.first().and_then(|image| image.mipmaps.last()) {
+        Some(mipmap) => mipmap,
+        None => return false,
+    };
+    if mipmap.width > NORMAL_SAMPLE_SIZE || mipmap.height > NORMAL_SAMPLE_SIZE {
+        return false;
+    }
+    let image = match tex.decode_mipmap(mipmap) {
+        Ok(image) => image,
+        Err(_) => return false,
+    };
+    let total = image.pixels().len();
+    let normal = image
+        .pixels()
+        .filter(|pixel| {
+            let [r, g, b, _] = pixel.0;
+            b >= 180 && r.abs_diff(128) < 80 && g.abs_diff(128) < 80
+        })
+        .count();
+    total > 0 && normal * 10 >= total * 9
+}
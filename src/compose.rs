@@ -0,0 +1,171 @@
+use crate::anim;
+use crate::scene::{ImageLayer, Scene, SceneObject};
+use crate::source::{self, Package};
+use crate::tex::{self, Tex};
+use image::{Rgba, RgbaImage};
+use std::collections::HashMap;
+
+// 软件合成：按 scene.json 的绘制顺序把可见图像图层以原始分辨率（正交投影尺寸）画到一张图上。
+// 支持位置、缩放、z 轴旋转、不透明度、颜色叠加与材质的混合模式，不执行着色器特效；
+// 动图纹理取第一帧，视频纹理跳过
+
+// 材质 blending 对应的混合方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Blend {
+    Alpha,    // translucent / normal：按透明度叠加
+    Additive, // additive：颜色相加
+    Multiply, // multiply：颜色相乘
+    Opaque,   // disabled / opaque：直接覆盖，忽略透明度
+}
+
+impl Blend {
+    fn from_name(name: &str) -> Blend {
+        match name.to_ascii_lowercase().as_str() {
+            "additive" => Blend::Additive,
+            "multiply" => Blend::Multiply,
+            "disabled" | "opaque" => Blend::Opaque,
+            _ => Blend::Alpha,
+        }
+    }
+}
+
+pub fn compose(scene: &Scene, packages: &[Package]) -> Result<RgbaImage, String> {
+    let (width, height) = scene
+        .canvas_size()
+        .ok_or_else(|| "场景没有固定的正交投影尺寸，无法合成".to_string())?;
+    let (width, height) = (width.round() as u32, height.round() as u32);
+    tex::check_dimensions(width, height)?;
+
+    let clear = scene
+        .general
+        .clear_color
+        .map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+    let mut canvas =
+        RgbaImage::from_pixel(width, height, Rgba([clear[0], clear[1], clear[2], 255]));
+    let mut textures: HashMap<String, Option<RgbaImage>> = HashMap::new();
+    for (object, layer) in scene.image_layers() {
+        if !scene.is_visible(object) || layer.alpha <= 0.0 {
+            continue;
+        }
+        let Some(texture) = layer.texture() else {
+            continue;
+        };
+        let image = textures
+            .entry(texture.to_string())
+            .or_insert_with(|| load_texture(packages, texture));
+        if let Some(image) = image {
+            draw_layer(&mut canvas, scene, object, layer, image);
+        }
+    }
+    Ok(canvas)
+}
+
+// 读取并解码 materials/<texture>.tex，无法解码时返回 None，跳过该图层
+fn load_texture(packages: &[Package], texture: &str) -> Option<RgbaImage> {
+    let name = format!("materials/{}.tex", texture);
+    let (package, index) = source::find_entry(packages, &name)?;
+    let data = package.read(index).ok()?;
+    let tex = Tex::parse(&data).ok()?;
+    if tex.mp4 {
+        return None;
+    }
+    if tex.frames.is_some() {
+        return anim::frames(&tex)
+            .ok()
+            .and_then(|frames| frames.into_iter().next())
+            .map(|frame| frame.image);
+    }
+    tex.decode().ok().map(|image| tex.crop_padding(image))
+}
+
+fn draw_layer(
+    canvas: &mut RgbaImage,
+    scene: &Scene,
+    object: &SceneObject,
+    layer: &ImageLayer,
+    image: &RgbaImage,
+) {
+    let Some(bounds) = scene.layer_bounds(object) else {
+        return;
+    };
+    let Some(size) = layer.size else {
+        return;
+    };
+    let width = size[0] * object.scale[0];
+    let height = size[1] * object.scale[1];
+    if width == 0.0 || height == 0.0 {
+        return;
+    }
+    let [center_x, center_y, _] = scene.world_origin(object);
+    let (sin, cos) = object.angles[2].sin_cos();
+    let blend = Blend::from_name(
+        layer
+            .material
+            .as_ref()
+            .map_or("", |material| material.blending.as_str()),
+    );
+    let canvas_height = canvas.height() as f32;
+
+    // 场景坐标 y 向上，图片坐标 y 向下
+    let left = bounds[0].floor().max(0.0) as u32;
+    let right = (bounds[2].ceil().max(0.0) as u32).min(canvas.width());
+    let top = (canvas_height - bounds[3]).floor().max(0.0) as u32;
+    let bottom = ((canvas_height - bounds[1]).ceil().max(0.0) as u32).min(canvas.height());
+    for py in top..bottom {
+        for px in left..right {
+            // 像素中心换算到图层局部坐标，再换算到纹理坐标 (u, v)，v 向下
+            let dx = px as f32 + 0.5 - center_x;
+            let dy = canvas_height - (py as f32 + 0.5) - center_y;
+            let local_x = dx * cos + dy * sin;
+            let local_y = -dx * sin + dy * cos;
+            let u = local_x / width + 0.5;
+            let v = 0.5 - local_y / height;
+            if !(0.0..1.0).contains(&u) || !(0.0..1.0).contains(&v) {
+                continue;
+            }
+            let mut color = sample(image, u, v);
+            for (channel, tint) in color.iter_mut().zip(layer.color) {
+                *channel *= tint;
+            }
+            color[3] *= layer.alpha.clamp(0.0, 1.0);
+            let target = canvas.get_pixel_mut(px, py);
+            blend_pixel(target, color, blend);
+        }
+    }
+}
+
+// 双线性采样，返回 0..1 的 RGBA
+fn sample(image: &RgbaImage, u: f32, v: f32) -> [f32; 4] {
+    let x = (u * image.width() as f32 - 0.5).max(0.0);
+    let y = (v * image.height() as f32 - 0.5).max(0.0);
+    let x0 = (x as u32).min(image.width() - 1);
+    let y0 = (y as u32).min(image.height() - 1);
+    let x1 = (x0 + 1).min(image.width() - 1);
+    let y1 = (y0 + 1).min(image.height() - 1);
+    let fx = x - x0 as f32;
+    let fy = y - y0 as f32;
+    let mut result = [0.0; 4];
+    for (index, value) in result.iter_mut().enumerate() {
+        let p = |x, y| image.get_pixel(x, y)[index] as f32 / 255.0;
+        let top = p(x0, y0) * (1.0 - fx) + p(x1, y0) * fx;
+        let bottom = p(x0, y1) * (1.0 - fx) + p(x1, y1) * fx;
+        *value = top * (1.0 - fy) + bottom * fy;
+    }
+    result
+}
+
+fn blend_pixel(target: &mut Rgba<u8>, color: [f32; 4], blend: Blend) {
+    let alpha = color[3].clamp(0.0, 1.0);
+    for index in 0..3 {
+        let base = target[index] as f32 / 255.0;
+        let value = color[index].clamp(0.0, 1.0);
+        let mixed = match blend {
+            Blend::Alpha => value * alpha + base * (1.0 - alpha),
+            Blend::Additive => base + value * alpha,
+            Blend::Multiply => base * (1.0 - alpha + value * alpha),
+            Blend::Opaque => value,
+        };
+        target[index] = (mixed.clamp(0.0, 1.0) * 255.0).round() as u8;
+    }
+    target[3] = 255;
+}
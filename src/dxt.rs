@@ -0,0 +1,275 @@
+use crate::error::ParseError;
+
+// DXT1/DXT3/DXT5（BC1/BC2/BC3）块解压，输出 RGBA8
+// 调用方需先校验尺寸上限（见 tex::check_dimensions），此处不再检查乘法溢出
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DxtKind {
+    Dxt1,
+    Dxt3,
+    Dxt5,
+}
+
+impl DxtKind {
+    // 每个 4x4 块占用的字节数
+    pub fn block_size(self) -> usize {
+        match self {
+            DxtKind::Dxt1 => 8,
+            DxtKind::Dxt3 | DxtKind::Dxt5 => 16,
+        }
+    }
+}
+
+// 给定尺寸下压缩数据应有的字节数
+pub fn compressed_size(kind: DxtKind, width: usize, height: usize) -> usize {
+    width.div_ceil(4) * height.div_ceil(4) * kind.block_size()
+}
+
+pub fn decompress(
+    kind: DxtKind,
+    data: &[u8],
+    width: usize,
+    height: usize,
+) -> Result<Vec<u8>, ParseError> {
+    let expected = compressed_size(kind, width, height);
+    if data.len() < expected {
+        return Err(ParseError::DataTooShort {
+            what: format!("{:?} {}x{}", kind, width, height),
+            expected,
+            actual: data.len(),
+        });
+    }
+
+    let mut rgba = vec![0u8; width * height * 4];
+    let blocks_x = width.div_ceil(4);
+    let block_size = kind.block_size();
+    for (index, block) in data[..expected].chunks_exact(block_size).enumerate() {
+        let mut pixels = [[0u8; 4]; 16];
+        match kind {
+            DxtKind::Dxt1 => decode_color_block(block, &mut pixels, true),
+            DxtKind::Dxt3 => {
+                decode_color_block(&block[8..], &mut pixels, false);
+                decode_explicit_alpha(&block[..8], &mut pixels);
+            }
+            DxtKind::Dxt5 => {
+                decode_color_block(&block[8..], &mut pixels, false);
+                decode_interpolated_alpha(&block[..8], &mut pixels);
+            }
+        }
+
+        // 将块内像素写回图像，超出图像边界的部分丢弃
+        let bx = (index % blocks_x) * 4;
+        let by = (index / blocks_x) * 4;
+        for (i, pixel) in pixels.iter().enumerate() {
+            let x = bx + i % 4;
+            let y = by + i / 4;
+            if x < width && y < height {
+                let offset = (y * width + x) * 4;
+                rgba[offset..offset + 4].copy_from_slice(pixel);
+            }
+        }
+    }
+    Ok(rgba)
+}
+
+fn rgb565(value: u16) -> [u8; 3] {
+    let r = ((value >> 11) & 0x1f) as u32;
+    let g = ((value >> 5) & 0x3f) as u32;
+    let b = (value & 0x1f) as u32;
+    [
+        ((r * 255 + 15) / 31) as u8,
+        ((g * 255 + 31) / 63) as u8,
+        ((b * 255 + 15) / 31) as u8,
+    ]
+}
+
+// 颜色块：两个 RGB565 端点 + 16 个 2 位索引
+fn decode_color_block(block: &[u8], pixels: &mut [[u8; 4]; 16], allow_alpha: bool) {
+    let c0 = u16::from_le_bytes([block[0], block[1]]);
+    let c1 = u16::from_le_bytes([block[2], block[3]]);
+    let e0 = rgb565(c0);
+    let e1 = rgb565(c1);
+
+    let mut palette = [[0u8; 4]; 4];
+    palette[0] = [e0[0], e0[1], e0[2], 255];
+    palette[1] = [e1[0], e1[1], e1[2], 255];
+    if c0 > c1 || !allow_alpha {
+        for ch in 0..3 {
+            let a = e0[ch] as u32;
+            let b = e1[ch] as u32;
+            palette[2][ch] = ((2 * a + b) / 3) as u8;
+            palette[3][ch] = ((a + 2 * b) / 3) as u8;
+        }
+        palette[2][3] = 255;
+        palette[3][3] = 255;
+    } else {
+        // DXT1 三色模式，索引 3 为全透明黑色
+        for ch in 0..3 {
+            palette[2][ch] = ((e0[ch] as u32 + e1[ch] as u32) / 2) as u8;
+        }
+        palette[2][3] = 255;
+        palette[3] = [0, 0, 0, 0];
+    }
+
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+    for (i, pixel) in pixels.iter_mut().enumerate() {
+        *pixel = palette[((indices >> (2 * i)) & 0x3) as usize];
+    }
+}
+
+// DXT3：每像素 4 位显式 alpha
+fn decode_explicit_alpha(block: &[u8], pixels: &mut [[u8; 4]; 16]) {
+    for (i, pixel) in pixels.iter_mut().enumerate() {
+        let byte = block[i / 2];
+        let nibble = if i % 2 == 0 { byte & 0x0f } else { byte >> 4 };
+        pixel[3] = nibble * 17;
+    }
+}
+
+// DXT5：两个 alpha 端点 + 16 个 3 位索引
+fn decode_interpolated_alpha(block: &[u8], pixels: &mut [[u8; 4]; 16]) {
+    let a0 = block[0] as u32;
+    let a1 = block[1] as u32;
+    let mut palette = [0u8; 8];
+    palette[0] = a0 as u8;
+    palette[1] = a1 as u8;
+    if a0 > a1 {
+        for i in 1..7 {
+            palette[i + 1] = (((7 - i as u32) * a0 + i as u32 * a1) / 7) as u8;
+        }
+    } else {
+        for i in 1..5 {
+            palette[i + 1] = (((5 - i as u32) * a0 + i as u32 * a1) / 5) as u8;
+        }
+        palette[6] = 0;
+        palette[7] = 255;
+    }
+
+    let mut bits = 0u64;
+    for (i, byte) in block[2..8].iter().enumerate() {
+        bits |= (*byte as u64) << (8 * i);
+    }
+    for (i, pixel) in pixels.iter_mut().enumerate() {
+        pixel[3] = palette[((bits >> (3 * i)) & 0x7) as usize];
+    }
+}
+
+// DXT5 压缩：颜色取包围盒端点，alpha 取最小/最大值，各像素选最接近的调色板索引
+pub fn compress_dxt5(rgba: &[u8], width: usize, height: usize) -> Vec<u8> {
+    let blocks_x = width.div_ceil(4);
+    let blocks_y = height.div_ceil(4);
+    let mut out = Vec::with_capacity(blocks_x * blocks_y * 16);
+    for by in 0..blocks_y {
+        for bx in 0..blocks_x {
+            // 取出 4x4 块，超出边界时重复边缘像素
+            let mut pixels = [[0u8; 4]; 16];
+            for (i, pixel) in pixels.iter_mut().enumerate() {
+                let x = (bx * 4 + i % 4).min(width - 1);
+                let y = (by * 4 + i / 4).min(height - 1);
+                let offset = (y * width + x) * 4;
+                pixel.copy_from_slice(&rgba[offset..offset + 4]);
+            }
+            encode_alpha_block(&pixels, &mut out);
+            encode_color_block(&pixels, &mut out);
+        }
+    }
+    out
+}
+
+fn to_rgb565(color: [u8; 3]) -> u16 {
+    let r = (color[0] as u16 * 31 + 127) / 255;
+    let g = (color[1] as u16 * 63 + 127) / 255;
+    let b = (color[2] as u16 * 31 + 127) / 255;
+    (r << 11) | (g << 5) | b
+}
+
+fn encode_color_block(pixels: &[[u8; 4]; 16], out: &mut Vec<u8>) {
+    let mut min = [255u8; 3];
+    let mut max = [0u8; 3];
+    for pixel in pixels {
+        for ch in 0..3 {
+            min[ch] = min[ch].min(pixel[ch]);
+            max[ch] = max[ch].max(pixel[ch]);
+        }
+    }
+    // 端点取包围盒对角线：某通道与变化最大的通道反向变化时，交换该通道的最小、最大值
+    let main = (0..3).max_by_key(|&ch| max[ch] - min[ch]).unwrap_or(0);
+    let mut mean = [0i32; 3];
+    for pixel in pixels {
+        for ch in 0..3 {
+            mean[ch] += pixel[ch] as i32;
+        }
+    }
+    let mean = mean.map(|sum| sum / 16);
+    for ch in 0..3 {
+        let covariance: i32 = pixels
+            .iter()
+            .map(|pixel| (pixel[main] as i32 - mean[main]) * (pixel[ch] as i32 - mean[ch]))
+            .sum();
+        if covariance < 0 {
+            std::mem::swap(&mut min[ch], &mut max[ch]);
+        }
+    }
+    let mut c0 = to_rgb565(max);
+    let mut c1 = to_rgb565(min);
+    if c0 < c1 {
+        std::mem::swap(&mut c0, &mut c1);
+    }
+
+    let e0 = rgb565(c0);
+    let e1 = rgb565(c1);
+    let mut palette = [[0u32; 3]; 4];
+    for ch in 0..3 {
+        let a = e0[ch] as u32;
+        let b = e1[ch] as u32;
+        palette[0][ch] = a;
+        palette[1][ch] = b;
+        palette[2][ch] = (2 * a + b) / 3;
+        palette[3][ch] = (a + 2 * b) / 3;
+    }
+
+    let mut indices = 0u32;
+    if c0 != c1 {
+        for (i, pixel) in pixels.iter().enumerate() {
+            let best = (0..4)
+                .min_by_key(|&p| {
+                    (0..3)
+                        .map(|ch| {
+                            let d = pixel[ch] as i32 - palette[p][ch] as i32;
+                            d * d
+                        })
+                        .sum::<i32>()
+                })
+                .unwrap_or(0);
+            indices |= (best as u32) << (2 * i);
+        }
+    }
+    out.extend_from_slice(&c0.to_le_bytes());
+    out.extend_from_slice(&c1.to_le_bytes());
+    out.extend_from_slice(&indices.to_le_bytes());
+}
+
+fn encode_alpha_block(pixels: &[[u8; 4]; 16], out: &mut Vec<u8>) {
+    let a0 = pixels.iter().map(|p| p[3]).max().unwrap_or(255);
+    let a1 = pixels.iter().map(|p| p[3]).min().unwrap_or(255);
+
+    let mut palette = [0u8; 8];
+    palette[0] = a0;
+    palette[1] = a1;
+    for i in 1..7u32 {
+        palette[i as usize + 1] = (((7 - i) * a0 as u32 + i * a1 as u32) / 7) as u8;
+    }
+
+    let mut bits = 0u64;
+    if a0 != a1 {
+        for (i, pixel) in pixels.iter().enumerate() {
+            let best = (0..8)
+                .min_by_key(|&p| (pixel[3] as i32 - palette[p] as i32).abs())
+                .unwrap_or(0);
+            bits |= (best as u64) << (3 * i);
+        }
+    }
+    out.push(a0);
+    out.push(a1);
+    out.extend_from_slice(&bits.to_le_bytes()[..6]);
+}
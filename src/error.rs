@@ -0,0 +1,127 @@
+use std::fmt;
+
+// pkg / tex 解析错误。解析器遇到异常输入时返回这些错误而不是 panic，
+// 上层 I/O 流程仍使用 String 错误，可通过 ? 自动转换
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    // 读取越过数据末尾
+    UnexpectedEof {
+        offset: usize,
+        needed: usize,
+        remaining: usize,
+    },
+    // 文件头或段头不符合预期
+    BadMagic {
+        expected: &'static str,
+        found: String,
+    },
+    // 可识别但不在支持矩阵中的版本
+    UnsupportedVersion {
+        what: &'static str,
+        version: String,
+    },
+    // 不支持的格式或功能
+    Unsupported(String),
+    // 字符串长度超过上限
+    StringTooLong {
+        len: usize,
+        max: usize,
+    },
+    // 数量（条目、图片、mipmap、帧）超过上限或超出文件大小
+    CountTooLarge {
+        what: &'static str,
+        count: u64,
+        max: u64,
+    },
+    // 条目区间超出数据区
+    OutOfBounds {
+        name: String,
+        offset: u64,
+        length: u64,
+        size: u64,
+    },
+    // 尺寸为 0 或超过上限
+    BadDimensions {
+        width: u32,
+        height: u32,
+    },
+    // 像素数据比尺寸所需的短
+    DataTooShort {
+        what: String,
+        expected: usize,
+        actual: usize,
+    },
+    // 申请的内存超过上限
+    TooLarge {
+        requested: u64,
+        limit: u64,
+    },
+    // tex 中没有可解码的图片
+    NoImage,
+    // 压缩数据或内嵌图片解码失败
+    Decode(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::UnexpectedEof {
+                offset,
+                needed,
+                remaining,
+            } => write!(
+                f,
+                "数据不足：位置 {} 需要 {} 字节，剩余 {} 字节",
+                offset, needed, remaining
+            ),
+            ParseError::BadMagic { expected, found } => {
+                write!(f, "不是有效的 {} 数据，头部为 {:?}", expected, found)
+            }
+            ParseError::UnsupportedVersion { what, version } => {
+                write!(f, "不支持的{}版本 {:?}", what, version)
+            }
+            ParseError::Unsupported(message) => write!(f, "{}", message),
+            ParseError::StringTooLong { len, max } => {
+                write!(f, "字符串长度 {} 超过上限 {}", len, max)
+            }
+            ParseError::CountTooLarge { what, count, max } => {
+                write!(f, "{}数量 {} 超过上限 {}", what, count, max)
+            }
+            ParseError::OutOfBounds {
+                name,
+                offset,
+                length,
+                size,
+            } => write!(
+                f,
+                "条目 {} 超出数据区范围（偏移 {}，长度 {}，数据区 {} 字节）",
+                name, offset, length, size
+            ),
+            ParseError::BadDimensions { width, height } => {
+                write!(f, "图片尺寸 {}x{} 无效", width, height)
+            }
+            ParseError::DataTooShort {
+                what,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{} 数据长度不足：需要 {} 字节，实际 {} 字节",
+                what, expected, actual
+            ),
+            ParseError::TooLarge { requested, limit } => {
+                write!(f, "需要 {} 字节内存，超过上限 {} 字节", requested, limit)
+            }
+            ParseError::NoImage => write!(f, "tex 中没有图片数据"),
+            ParseError::Decode(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl From<ParseError> for String {
+    fn from(error: ParseError) -> String {
+        error.to_string()
+    }
+}
@@ -0,0 +1,239 @@
+use crate::scene::{ObjectKind, Scene};
+use crate::source::{self, Package};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+
+// 引用关系：scene.json 中的对象 → 模型 / 粒子 / 特效 json → 材质 → 纹理。
+// 用于判断哪些纹理被使用、被哪个对象使用，以及哪些 tex 没有被任何对象引用
+
+#[derive(Serialize, Debug, Default)]
+pub struct ReferenceGraph {
+    pub objects: Vec<ObjectRefs>,
+    pub textures: Vec<TextureRefs>, // 被引用的纹理，按条目路径排序
+    pub orphans: Vec<String>,       // 没有被引用的 tex 条目
+}
+
+#[derive(Serialize, Debug)]
+pub struct ObjectRefs {
+    pub id: i64,
+    pub name: String,
+    pub files: Vec<String>,    // 依次引用的模型、粒子、特效与材质 json
+    pub textures: Vec<String>, // 使用的纹理条目
+}
+
+#[derive(Serialize, Debug)]
+pub struct TextureRefs {
+    pub entry: String, // 条目路径，如 materials/bg.tex
+    pub exists: bool,  // 壁纸中是否存在该条目
+    pub users: Vec<TextureUser>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct TextureUser {
+    pub object: i64,
+    pub name: String,     // 对象名称
+    pub material: String, // 引用该纹理的材质 json
+    pub slot: usize,      // 纹理槽位
+    pub main: bool,       // 是否为图像图层显示的图片（材质第一个 pass 的第一个纹理）
+}
+
+impl ReferenceGraph {
+    // 纹理的主要使用者：优先作为图层图片使用的对象，其次第一个引用它的对象
+    pub fn primary_user(&self, entry: &str) -> Option<&TextureUser> {
+        let texture = self
+            .textures
+            .iter()
+            .find(|texture| texture.entry.eq_ignore_ascii_case(entry))?;
+        texture
+            .users
+            .iter()
+            .find(|user| user.main)
+            .or_else(|| texture.users.first())
+    }
+
+    pub fn is_orphan(&self, entry: &str) -> bool {
+        self.orphans
+            .iter()
+            .any(|orphan| orphan.eq_ignore_ascii_case(entry))
+    }
+}
+
+pub fn build(scene: &Scene, packages: &[Package]) -> ReferenceGraph {
+    let mut builder = Builder {
+        packages,
+        textures: BTreeMap::new(),
+    };
+    let mut objects = Vec::new();
+    for object in &scene.objects {
+        let mut refs = ObjectRefs {
+            id: object.id,
+            name: object.name.clone(),
+            files: Vec::new(),
+            textures: Vec::new(),
+        };
+        let user = (object.id, object.name.as_str());
+        match &object.kind {
+            ObjectKind::Image(layer) => {
+                builder.visit_owner(&layer.model, None, true, user, &mut refs)
+            }
+            ObjectKind::Particle { file } => {
+                builder.visit_owner(file, None, false, user, &mut refs)
+            }
+            _ => {}
+        }
+        for effect in &object.effects {
+            builder.visit_effect(&effect.file, &effect.textures, user, &mut refs);
+        }
+        objects.push(refs);
+    }
+
+    let referenced: BTreeSet<String> = builder.textures.keys().cloned().collect();
+    let mut orphans = Vec::new();
+    for package in packages {
+        for entry in &package.entries {
+            let key = entry.name.replace('\\', "/").to_ascii_lowercase();
+            if key.ends_with(".tex") && !referenced.contains(&key) {
+                orphans.push(entry.name.clone());
+            }
+        }
+    }
+    ReferenceGraph {
+        objects,
+        textures: builder.textures.into_values().collect(),
+        orphans,
+    }
+}
+
+struct Builder<'a> {
+    packages: &'a [Package],
+    textures: BTreeMap<String, TextureRefs>, // 键为小写的条目路径
+}
+
+impl Builder<'_> {
+    fn read_json(&self, name: &str) -> Option<Value> {
+        let (package, index) = source::find_entry(self.packages, name)?;
+        let data = package.read(index).ok()?;
+        serde_json::from_slice(&data).ok()
+    }
+
+    // 模型或粒子 json 通过 "material" 引用材质
+    fn visit_owner(
+        &mut self,
+        file: &str,
+        overrides: Option<&[Option<String>]>,
+        main: bool,
+        user: (i64, &str),
+        refs: &mut ObjectRefs,
+    ) {
+        refs.files.push(file.to_string());
+        let material = self.read_json(file).and_then(|json| {
+            json.get("material")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        });
+        if let Some(material) = material {
+            self.visit_material(&material, overrides, main, user, refs);
+        }
+    }
+
+    // 特效 json 的每个 pass 引用一个材质，scene.json 中对应 pass 的 textures 可覆盖材质中的纹理
+    fn visit_effect(
+        &mut self,
+        file: &str,
+        overrides: &[Vec<Option<String>>],
+        user: (i64, &str),
+        refs: &mut ObjectRefs,
+    ) {
+        refs.files.push(file.to_string());
+        let Some(json) = self.read_json(file) else {
+            return;
+        };
+        let passes = json
+            .get("passes")
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default();
+        for (index, pass) in passes.iter().enumerate() {
+            if let Some(material) = pass.get("material").and_then(Value::as_str) {
+                let overrides = overrides.get(index).map(Vec::as_slice);
+                self.visit_material(material, overrides, false, user, refs);
+            }
+        }
+    }
+
+    fn visit_material(
+        &mut self,
+        file: &str,
+        overrides: Option<&[Option<String>]>,
+        main: bool,
+        user: (i64, &str),
+        refs: &mut ObjectRefs,
+    ) {
+        refs.files.push(file.to_string());
+        let Some(json) = self.read_json(file) else {
+            return;
+        };
+        let passes = json
+            .get("passes")
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default();
+        for (pass_index, pass) in passes.iter().enumerate() {
+            let mut textures: Vec<Option<String>> = pass
+                .get("textures")
+                .and_then(Value::as_array)
+                .map(|items| {
+                    items
+                        .iter()
+                        .map(|item| item.as_str().map(str::to_string))
+                        .collect()
+                })
+                .unwrap_or_default();
+            // 覆盖只作用于第一个 pass
+            if let (0, Some(overrides)) = (pass_index, overrides) {
+                if textures.len() < overrides.len() {
+                    textures.resize(overrides.len(), None);
+                }
+                for (slot, texture) in overrides.iter().enumerate() {
+                    if texture.is_some() {
+                        textures[slot] = texture.clone();
+                    }
+                }
+            }
+            for (slot, texture) in textures.iter().enumerate() {
+                let Some(texture) = texture else {
+                    continue;
+                };
+                let entry = format!("materials/{}.tex", texture);
+                self.add_use(
+                    &entry,
+                    TextureUser {
+                        object: user.0,
+                        name: user.1.to_string(),
+                        material: file.to_string(),
+                        slot,
+                        main: main && pass_index == 0 && slot == 0,
+                    },
+                );
+                if !refs.textures.contains(&entry) {
+                    refs.textures.push(entry);
+                }
+            }
+        }
+    }
+
+    fn add_use(&mut self, entry: &str, user: TextureUser) {
+        let key = entry.to_ascii_lowercase();
+        let exists = source::find_entry(self.packages, entry).is_some();
+        self.textures
+            .entry(key)
+            .or_insert_with(|| TextureRefs {
+                entry: entry.to_string(),
+                exists,
+                users: Vec::new(),
+            })
+            .users
+            .push(user);
+    }
+}
@@ -1,11 +1,15 @@
+use crate::info;
 use crate::os;
+use crate::pkg;
 use crate::re;
+use crate::tex;
 use eframe::egui::ComboBox;
 use eframe::{
     egui::{self, ecolor::HexColor},
     NativeOptions,
 };
 use std::collections::HashMap;
+use std::path::Path;
 
 pub fn configure_fonts(ctx: &egui::Context) {
     use egui::{FontData, FontDefinitions, FontFamily};
@@ -48,7 +52,6 @@ struct ParamCheck {
     message: String, // 提示信息
 }
 
-#[derive(Default)]
 pub struct RepkgApp {
     pub target: String,    // 指定目录
     pub saved: String,     // 保存目录
@@ -56,12 +59,58 @@ pub struct RepkgApp {
     pub all_combine: bool, // 所有文件合并到一个文件夹
     pub cobo_status: usize,
     pub addition_suffix: Vec<String>, // 需要添加保存的后缀名称
+    pub anim_status: usize,           // 动图导出格式
+    pub keep_padding: bool,           // 保留纹理补齐区域
+    pub mip_status: usize,            // mipmap 层级
+    pub mip_size: u32,                // 最接近的尺寸（像素）
+    pub raw_dump: bool,               // 导出无法转换纹理的原始数据
+    pub alpha_status: usize,          // 透明通道处理
+    pub matte_color: [u8; 3],         // 填充底色
+    pub artwork_only: bool,           // 只提取作品纹理
+    pub main_only: bool,              // 只导出主背景图
+    pub compose: bool,                // 另外输出合成的场景图
+    pub name_by_object: bool,         // 按使用对象命名
+    pub skip_orphans: bool,           // 跳过未引用的纹理
 
-    search_results: Vec<String>, // 搜索结果
+    search_results: Vec<String>, // 搜索结果，提取时的错误信息
+    versions: Vec<String>,       // 提取时检测到的各壁纸 pkg/tex 版本
+    raw_dumps: Vec<String>,      // 提取时原样导出的纹理
+    skipped: Vec<String>,        // 提取时跳过的辅助纹理
     status_message: String,      // 状态信息
     message: Option<String>,
 }
 
+impl Default for RepkgApp {
+    fn default() -> Self {
+        RepkgApp {
+            target: String::new(),
+            saved: String::new(),
+            as_title: false,
+            all_combine: false,
+            cobo_status: 0,
+            addition_suffix: Vec::new(),
+            anim_status: 0,
+            keep_padding: false,
+            mip_status: 0,
+            mip_size: tex::DEFAULT_MIP_SIZE,
+            raw_dump: false,
+            alpha_status: 0,
+            matte_color: [0, 0, 0],
+            artwork_only: false,
+            main_only: false,
+            compose: false,
+            name_by_object: false,
+            skip_orphans: false,
+            search_results: Vec::new(),
+            versions: Vec::new(),
+            raw_dumps: Vec::new(),
+            skipped: Vec::new(),
+            status_message: String::new(),
+            message: None,
+        }
+    }
+}
+
 impl RepkgApp {
     // 根据 feature_state 显示对应的字符串
     fn cobo_status_to_str(&self) -> &'static str {
@@ -73,6 +122,32 @@ impl RepkgApp {
         }
     }
 
+    fn mip_status_to_str(&self) -> &'static str {
+        match self.mip_status {
+            1 => "全部层级",
+            2 => "最接近尺寸",
+            _ => "最大",
+        }
+    }
+
+    fn alpha_status_to_str(&self) -> &'static str {
+        match self.alpha_status {
+            1 => "填充底色",
+            2 => "反预乘",
+            _ => "保留",
+        }
+    }
+
+    fn anim_status_to_str(&self) -> &'static str {
+        match self.anim_status {
+            1 => "GIF",
+            2 => "APNG",
+            3 => "WebP",
+            4 => "帧序列",
+            _ => "精灵图",
+        }
+    }
+
     // 从字符串转换成对应的 usize 值
     fn str_to_cobo_status(state: &str) -> usize {
         match state {
@@ -104,6 +179,12 @@ impl eframe::App for RepkgApp {
                         }
                     }
                 }
+                // 也可以直接选择单个 pkg 或壁纸备份 zip
+                if ui.button("Select File").clicked() {
+                    if let Ok(path) = os::pick_file() {
+                        self.target = path;
+                    }
+                }
                 ui.add_space(10.0); // 可选：在两个输入框之间增加间距
                 
             });
@@ -139,6 +220,65 @@ impl eframe::App for RepkgApp {
             });
             ui.separator();
 
+            ui.horizontal(|ui| {
+                ComboBox::from_label("动图导出")
+                    .selected_text(self.anim_status_to_str())
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.anim_status, 0, "精灵图");
+                        ui.selectable_value(&mut self.anim_status, 1, "GIF");
+                        ui.selectable_value(&mut self.anim_status, 2, "APNG");
+                        ui.selectable_value(&mut self.anim_status, 3, "WebP");
+                        ui.selectable_value(&mut self.anim_status, 4, "帧序列");
+                    });
+                ui.add_space(30.0);
+                ui.checkbox(&mut self.keep_padding, "保留补齐区域");
+                ui.add_space(30.0);
+                ui.checkbox(&mut self.raw_dump, "导出无法转换的纹理原始数据");
+                ui.add_space(30.0);
+                ui.checkbox(&mut self.artwork_only, "跳过遮罩、法线等辅助纹理");
+                ui.add_space(30.0);
+                ui.checkbox(&mut self.main_only, "仅导出主图（以壁纸名称命名）");
+                ui.add_space(30.0);
+                ui.checkbox(&mut self.compose, "合成场景图");
+            });
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.name_by_object, "纹理按使用对象命名");
+                ui.add_space(30.0);
+                ui.checkbox(&mut self.skip_orphans, "跳过未被场景引用的纹理");
+            });
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                ComboBox::from_label("Mipmap 层级")
+                    .selected_text(self.mip_status_to_str())
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.mip_status, 0, "最大");
+                        ui.selectable_value(&mut self.mip_status, 1, "全部层级");
+                        ui.selectable_value(&mut self.mip_status, 2, "最接近尺寸");
+                    });
+                if self.mip_status == 2 {
+                    ui.add(
+                        egui::DragValue::new(&mut self.mip_size)
+                            .clamp_range(16..=8192)
+                            .suffix(" px"),
+                    );
+                }
+                ui.add_space(30.0);
+                ComboBox::from_label("透明通道")
+                    .selected_text(self.alpha_status_to_str())
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.alpha_status, 0, "保留");
+                        ui.selectable_value(&mut self.alpha_status, 1, "填充底色");
+                        ui.selectable_value(&mut self.alpha_status, 2, "反预乘");
+                    });
+                if self.alpha_status == 1 {
+                    ui.color_edit_button_srgb(&mut self.matte_color);
+                }
+            });
+            ui.separator();
+
             // 按钮触发搜索
             if ui.button("开始转换").clicked() {
                 let check_param = check_search_param(&self.target, &self.saved);
@@ -154,11 +294,31 @@ impl eframe::App for RepkgApp {
                         all_combine: self.all_combine,
                         cobo_status: self.cobo_status,
                         addition_suffix: self.addition_suffix.clone(),
+                        anim_status: self.anim_status,
+                        keep_padding: self.keep_padding,
+                        mip_status: self.mip_status,
+                        mip_size: self.mip_size,
+                        raw_dump: self.raw_dump,
+                        alpha_status: self.alpha_status,
+                        matte_color: self.matte_color,
+                        artwork_only: self.artwork_only,
+                        main_only: self.main_only,
+                        compose: self.compose,
+                        name_by_object: self.name_by_object,
+                        skip_orphans: self.skip_orphans,
                     };
 
                     match re::extract(argumets) {
                         Ok(s) => {
-                            self.status_message = format!("提取到【{}】个文件。", s);
+                            self.status_message = format!("提取到【{}】个文件。", s.file_count);
+                            if !s.errors.is_empty() {
+                                self.status_message
+                                    .push_str(&format!("【{}】个错误：", s.errors.len()));
+                            }
+                            self.search_results = s.errors;
+                            self.versions = s.versions;
+                            self.raw_dumps = s.raw_dumps;
+                            self.skipped = s.skipped;
                         }
                         Err(e) => {
                             self.status_message = format!("提取出错：{}", e);
@@ -167,9 +327,103 @@ impl eframe::App for RepkgApp {
                 }
             }
 
+            // 将指定目录中的素材打包为提取结果存放目录下的 scene.pkg
+            if ui.button("打包为 pkg").clicked() {
+                let check_param = check_search_param(&self.target, &self.saved);
+                if check_param.status == 1 {
+                    self.message = Some(check_param.message);
+                } else {
+                    let output = Path::new(&self.saved).join("scene.pkg");
+                    match pkg::pack_dir(Path::new(&self.target), &output, None) {
+                        Ok(count) => {
+                            self.status_message =
+                                format!("已打包【{}】个文件至 {}", count, output.to_string_lossy());
+                        }
+                        Err(e) => {
+                            self.status_message = format!("打包出错：{}", e);
+                        }
+                    }
+                    self.search_results.clear();
+                    self.versions.clear();
+                    self.raw_dumps.clear();
+                    self.skipped.clear();
+                }
+            }
+
+            // 生成壁纸信息报告，保存为提取结果存放目录下的 info.json
+            if ui.button("查看信息").clicked() {
+                let check_param = check_search_param(&self.target, &self.saved);
+                if check_param.status == 1 {
+                    self.message = Some(check_param.message);
+                } else {
+                    let output = Path::new(&self.saved).join("info.json");
+                    match info::save_report(Path::new(&self.target), &output) {
+                        Ok(count) => {
+                            self.status_message = format!(
+                                "共【{}】个壁纸，报告已保存至 {}",
+                                count,
+                                output.to_string_lossy()
+                            );
+                        }
+                        Err(e) => {
+                            self.status_message = format!("生成报告出错：{}", e);
+                        }
+                    }
+                    self.search_results.clear();
+                    self.versions.clear();
+                    self.raw_dumps.clear();
+                    self.skipped.clear();
+                }
+            }
+
             ui.separator();
             // 显示状态信息
             ui.label(&self.status_message);
+            if !self.search_results.is_empty() {
+                egui::ScrollArea::vertical()
+                    .max_height(160.0)
+                    .show(ui, |ui| {
+                        for err in &self.search_results {
+                            ui.label(err);
+                        }
+                    });
+            }
+            if !self.versions.is_empty() {
+                egui::CollapsingHeader::new("格式版本").show(ui, |ui| {
+                    egui::ScrollArea::vertical()
+                        .id_source("versions")
+                        .max_height(160.0)
+                        .show(ui, |ui| {
+                            for line in &self.versions {
+                                ui.label(line);
+                            }
+                        });
+                });
+            }
+            if !self.raw_dumps.is_empty() {
+                egui::CollapsingHeader::new("原样导出的纹理").show(ui, |ui| {
+                    egui::ScrollArea::vertical()
+                        .id_source("raw_dumps")
+                        .max_height(160.0)
+                        .show(ui, |ui| {
+                            for line in &self.raw_dumps {
+                                ui.label(line);
+                            }
+                        });
+                });
+            }
+            if !self.skipped.is_empty() {
+                egui::CollapsingHeader::new("跳过的辅助纹理").show(ui, |ui| {
+                    egui::ScrollArea::vertical()
+                        .id_source("skipped")
+                        .max_height(160.0)
+                        .show(ui, |ui| {
+                            for line in &self.skipped {
+                                ui.label(line);
+                            }
+                        });
+                });
+            }
 
             if let Some(mes) = self.message.clone() {
                 // 创建一个错误窗口，并设置位置和大小
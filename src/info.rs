@@ -0,0 +1,210 @@
+use crate::classify::Classifier;
+use crate::error::ParseError;
+use crate::graph::{self, ReferenceGraph};
+use crate::scene::Scene;
+use crate::source::{self, Package};
+use crate::tex::{self, Tex, TexFormat};
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+// 壁纸 / 壁纸库的结构化检查报告，对应 RePKG 的 info 模式
+
+#[derive(Serialize, Debug)]
+pub struct WallpaperInfo {
+    pub path: String,
+    pub title: Option<String>,
+    pub pkgs: Vec<PkgInfo>,
+    pub scene: Option<Scene>, // scene.json 解析结果，非场景壁纸为 None
+    pub scene_error: Option<String>, // scene.json 无法解析时的错误信息
+    pub main_layer: Option<i64>, // 主背景图层的对象 id
+    pub graph: Option<ReferenceGraph>, // 对象 → 模型 → 材质 → 纹理的引用关系
+}
+
+#[derive(Serialize, Debug)]
+pub struct PkgInfo {
+    pub path: String,
+    pub version: String,
+    pub entries: Vec<EntryInfo>,
+    pub error: Option<String>, // pkg 无法解析时的错误信息
+}
+
+#[derive(Serialize, Debug)]
+pub struct EntryInfo {
+    pub name: String,
+    pub size: u64,
+    pub texture: Option<TextureInfo>, // 仅 .tex 条目
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct TextureInfo {
+    pub version: String,
+    pub container: String,
+    pub format: String,
+    pub width: u32, // 实际图像尺寸
+    pub height: u32,
+    pub texture_width: u32, // 纹理尺寸
+    pub texture_height: u32,
+    pub images: usize,
+    pub mipmaps: usize,
+    pub lz4: bool,                // 是否有 LZ4 压缩的 mipmap
+    pub embedded: Option<String>, // 内嵌图片的扩展名
+    pub animated: bool,
+    pub frames: usize,
+    pub frames_version: Option<String>, // 动图帧信息版本 TEXS000x
+    pub role: Option<String>,           // 作品 / 辅助纹理及判断依据
+    pub error: Option<String>,          // tex 无法解析时的错误信息
+}
+
+// 检查单个壁纸目录、壁纸大目录、单个 pkg 文件或 zip 压缩包
+pub fn inspect(target: &Path) -> Result<Vec<WallpaperInfo>, String> {
+    let source = source::open(target)?;
+    let mut result = Vec::new();
+    for wallpaper in source.wallpapers() {
+        let mut info = WallpaperInfo {
+            path: wallpaper.path.clone(),
+            title: wallpaper.title.clone(),
+            pkgs: Vec::new(),
+            scene: None,
+            scene_error: None,
+            main_layer: None,
+            graph: None,
+        };
+        match source.open(&wallpaper) {
+            Ok(packages) => {
+                let classifier = Classifier::from_packages(&packages);
+                info.pkgs = packages
+                    .iter()
+                    .map(|package| inspect_package(package, &classifier))
+                    .collect();
+                match Scene::load(&packages) {
+                    Ok(scene) => {
+                        info.main_layer = scene
+                            .as_ref()
+                            .and_then(|scene| scene.main_layer())
+                            .map(|object| object.id);
+                        info.graph = scene.as_ref().map(|scene| graph::build(scene, &packages));
+                        info.scene = scene;
+                    }
+                    Err(e) => info.scene_error = Some(e),
+                }
+            }
+            Err(e) => info.pkgs.push(PkgInfo {
+                path: wallpaper.path,
+                version: String::new(),
+                entries: Vec::new(),
+                error: Some(e),
+            }),
+        }
+        result.push(info);
+    }
+    Ok(result)
+}
+
+pub fn inspect_json(target: &Path) -> Result<String, String> {
+    let report = inspect(target)?;
+    serde_json::to_string_pretty(&report).map_err(|e| format!("生成报告失败: {}", e))
+}
+
+// 散装文件没有 pkg 版本，version 为空
+// 纹理按同一壁纸（所有 pkg）中材质、特效 json 的引用分类
+fn inspect_package(package: &Package, classifier: &Classifier) -> PkgInfo {
+    let mut entries = Vec::new();
+    for (index, entry) in package.entries.iter().enumerate() {
+        let texture = if entry.name.ends_with(".tex") {
+            Some(match package.read(index) {
+                Ok(data) => match Tex::parse(&data) {
+                    Ok(tex) => {
+                        let mut info = texture_info(&tex);
+                        let (role, reason) = classifier.classify(&entry.name, &tex);
+                        info.role = Some(format!("{}（{}）", role.name(), reason));
+                        info
+                    }
+                    Err(e) => parse_error_info(&data, e),
+                },
+                Err(e) => TextureInfo {
+                    error: Some(e),
+                    ..Default::default()
+                },
+            })
+        } else {
+            None
+        };
+        entries.push(EntryInfo {
+            name: entry.name.clone(),
+            size: entry.size,
+            texture,
+        });
+    }
+    PkgInfo {
+        path: package.path.clone(),
+        version: package.version.clone().unwrap_or_default(),
+        entries,
+        error: None,
+    }
+}
+
+pub fn inspect_tex(data: &[u8]) -> TextureInfo {
+    match Tex::parse(data) {
+        Ok(tex) => texture_info(&tex),
+        Err(e) => parse_error_info(data, e),
+    }
+}
+
+// 无法解析时仍尽量给出各段版本，便于找出不受支持的格式
+fn parse_error_info(data: &[u8], error: ParseError) -> TextureInfo {
+    let versions = tex::detect_versions(data).unwrap_or_default();
+    let mut parts = versions.split('/');
+    TextureInfo {
+        version: parts.next().unwrap_or_default().to_string(),
+        container: parts.nth(1).unwrap_or_default().to_string(),
+        error: Some(error.to_string()),
+        ..Default::default()
+    }
+}
+
+fn texture_info(tex: &Tex) -> TextureInfo {
+    let header = &tex.header;
+    TextureInfo {
+        version: tex.magic.clone(),
+        container: tex.container.clone(),
+        format: format_name(header.format),
+        width: header.image_width,
+        height: header.image_height,
+        texture_width: header.texture_width,
+        texture_height: header.texture_height,
+        images: tex.images.len(),
+        mipmaps: tex.images.first().map_or(0, |image| image.mipmaps.len()),
+        lz4: tex
+            .images
+            .iter()
+            .flat_map(|image| &image.mipmaps)
+            .any(|mipmap| mipmap.lz4),
+        embedded: tex.embedded_extension().map(|ext| ext.to_string()),
+        animated: tex.is_gif(),
+        frames: tex.frames.as_ref().map_or(0, |frames| frames.frames.len()),
+        frames_version: tex.frames.as_ref().map(|frames| frames.magic.clone()),
+        role: None,
+        error: None,
+    }
+}
+
+fn format_name(format: TexFormat) -> String {
+    match format {
+        TexFormat::Rgba8888 => "RGBA8888".to_string(),
+        TexFormat::Dxt5 => "DXT5".to_string(),
+        TexFormat::Dxt3 => "DXT3".to_string(),
+        TexFormat::Dxt1 => "DXT1".to_string(),
+        TexFormat::Rg88 => "RG88".to_string(),
+        TexFormat::R8 => "R8".to_string(),
+        TexFormat::Unknown(value) => format!("Unknown({})", value),
+    }
+}
+
+// 生成报告并保存为 JSON 文件
+pub fn save_report(target: &Path, output: &Path) -> Result<usize, String> {
+    let report = inspect(target)?;
+    let text = serde_json::to_string_pretty(&report).map_err(|e| format!("生成报告失败: {}", e))?;
+    fs::write(output, text).map_err(|e| format!("写入 {:?} 失败: {}", output, e))?;
+    Ok(report.len())
+}
@@ -1,6 +1,19 @@
 pub mod os;
 pub mod gui;
 pub mod re;
+pub mod pkg;
+pub mod tex;
+pub mod anim;
+pub mod alpha;
+pub mod classify;
+pub mod scene;
+pub mod compose;
+pub mod graph;
+pub mod info;
+pub mod error;
+pub mod source;
+mod dxt;
+mod reader;
 use std::env;
 
 pub fn run() -> Result<(), eframe::Error>  {
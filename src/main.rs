@@ -1,6 +1,50 @@
-use repkg::{os, re};
+use repkg::{info, os, pkg, re, tex};
+use std::env;
+use std::path::Path;
 
 fn main() -> Result<(), eframe::Error> {
+    // 命令行：repkg info <壁纸目录|壁纸大目录|pkg 文件|zip 文件>，输出 JSON 报告
+    let args: Vec<String> = env::args().collect();
+    if args.len() >= 3 && args[1] == "info" {
+        match info::inspect_json(Path::new(&args[2])) {
+            Ok(report) => println!("{}", report),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+    // 命令行：repkg pack <素材目录> <输出 pkg> [版本]，将目录打包为 scene.pkg
+    if args.len() >= 4 && args[1] == "pack" {
+        let version = args.get(4).map(|v| v.as_str());
+        match pkg::pack_dir(Path::new(&args[2]), Path::new(&args[3]), version) {
+            Ok(count) => println!("已打包 {} 个文件至 {}", count, args[3]),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+    // 命令行：repkg encode <输入图片> <输出 tex> [rgba|dxt5] [--lz4] [--no-mipmaps]
+    if args.len() >= 4 && args[1] == "encode" {
+        let mut options = tex::EncodeOptions::default();
+        for arg in &args[4..] {
+            match arg.as_str() {
+                "rgba" => options.format = tex::TexFormat::Rgba8888,
+                "dxt5" => options.format = tex::TexFormat::Dxt5,
+                "--lz4" => options.lz4 = true,
+                "--no-mipmaps" => options.mipmaps = false,
+                other => eprintln!("忽略未知参数 {}", other),
+            }
+        }
+        if let Err(e) = tex::encode_file(Path::new(&args[2]), Path::new(&args[3]), &options) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
     repkg::run()
 }
 
@@ -15,5 +59,4 @@ fn demo() {
     // };
 
     // re::extract(param);
-    
 }
@@ -0,0 +1,315 @@
+use crate::error::ParseError;
+use crate::reader::Reader;
+use memmap2::Mmap;
+use std::borrow::Cow;
+use std::fs::{self, File};
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use walkdir::WalkDir;
+
+// scene.pkg 容器格式（小端）：
+//   int32 长度 + 版本字符串（如 PKGV0001）
+//   int32 条目数量
+//   每个条目：int32 长度 + 路径，int32 偏移，int32 长度
+//   条目表之后为数据区，条目偏移相对于数据区起点
+const MAX_MAGIC_LEN: usize = 32;
+const MAX_NAME_LEN: usize = 255;
+
+// 支持矩阵：已知的 pkg 版本，各版本的条目表布局相同；
+// 其余 PKGV 版本解析时报错并给出版本字符串
+pub const SUPPORTED_VERSIONS: &[&str] = &[
+    "PKGV0001", "PKGV0002", "PKGV0003", "PKGV0004", "PKGV0005", "PKGV0006", "PKGV0007", "PKGV0008",
+    "PKGV0009", "PKGV0010", "PKGV0011", "PKGV0012", "PKGV0013", "PKGV0014", "PKGV0015", "PKGV0016",
+    "PKGV0017", "PKGV0018", "PKGV0019", "PKGV0020", "PKGV0021", "PKGV0022",
+];
+
+#[derive(Debug, Clone)]
+pub struct PkgEntry {
+    pub name: String, // 条目路径，如 materials/bg.tex
+    pub offset: u32,  // 相对数据区的偏移
+    pub length: u32,  // 数据长度
+}
+
+// 条目数据的来源
+enum Storage {
+    Memory(Vec<u8>), // Pkg::parse 传入的完整数据
+    Mapped(Mmap),    // 内存映射整个文件，条目按需换入，不会整体读入内存
+    // 映射失败时的回退：只读入条目表，条目内容按需定位读取
+    File(Mutex<BufReader<File>>),
+}
+
+pub struct Pkg {
+    pub magic: String,          // 头部版本字符串
+    pub entries: Vec<PkgEntry>, // 条目表
+    storage: Storage,
+    data_start: usize, // 数据区在文件中的起点
+}
+
+// 缓冲读取时首次读入的头部长度，条目表更长时按倍数扩大
+const TABLE_CHUNK: usize = 64 * 1024;
+
+impl Pkg {
+    // 优先内存映射，映射失败（如空文件、不支持映射的文件系统）时回退为缓冲读取
+    pub fn open(path: &Path) -> Result<Pkg, String> {
+        let file = File::open(path).map_err(|e| format!("读取 {:?} 失败: {}", path, e))?;
+        // 映射期间文件若被其他程序改写，读到的内容可能不一致；条目读取均做边界检查
+        match unsafe { Mmap::map(&file) } {
+            Ok(map) => {
+                let (magic, entries, data_start) = parse_table(&map, map.len())
+                    .map_err(|e| format!("解析 {:?} 失败: {}", path, e))?;
+                Ok(Pkg {
+                    magic,
+                    entries,
+                    storage: Storage::Mapped(map),
+                    data_start,
+                })
+            }
+            Err(e) => {
+                eprintln!("内存映射 {:?} 失败，改为缓冲读取: {}", path, e);
+                Pkg::open_buffered(path)
+            }
+        }
+    }
+
+    // 不使用内存映射，只读入条目表
+    pub fn open_buffered(path: &Path) -> Result<Pkg, String> {
+        let file = File::open(path).map_err(|e| format!("读取 {:?} 失败: {}", path, e))?;
+        let size = file
+            .metadata()
+            .map_err(|e| format!("读取 {:?} 失败: {}", path, e))?
+            .len() as usize;
+        let mut reader = BufReader::new(file);
+        let mut head = Vec::new();
+        let mut want = TABLE_CHUNK.min(size);
+        let (magic, entries, data_start) = loop {
+            (&mut reader)
+                .take((want - head.len()) as u64)
+                .read_to_end(&mut head)
+                .map_err(|e| format!("读取 {:?} 失败: {}", path, e))?;
+            // 读到的长度不足说明文件在打开后被截短，继续扩大读取范围不会再有进展
+            if head.len() < want {
+                return Err(format!(
+                    "读取 {:?} 失败: 文件在读取期间被截短，只读到 {} / {} 字节",
+                    path,
+                    head.len(),
+                    size
+                ));
+            }
+            match parse_table(&head, size) {
+                Err(ParseError::UnexpectedEof { .. }) if head.len() < size => {
+                    want = (want * 2).min(size);
+                }
+                result => break result.map_err(|e| format!("解析 {:?} 失败: {}", path, e))?,
+            }
+        };
+        Ok(Pkg {
+            magic,
+            entries,
+            storage: Storage::File(Mutex::new(reader)),
+            data_start,
+        })
+    }
+
+    pub fn parse(data: Vec<u8>) -> Result<Pkg, ParseError> {
+        let (magic, entries, data_start) = parse_table(&data, data.len())?;
+        Ok(Pkg {
+            magic,
+            entries,
+            storage: Storage::Memory(data),
+            data_start,
+        })
+    }
+
+    // 读取条目内容；内存与映射方式直接借用，缓冲方式定位后读取
+    pub fn entry_data(&self, entry: &PkgEntry) -> Result<Cow<'_, [u8]>, String> {
+        let start = self.data_start as u64 + entry.offset as u64;
+        let end = start + entry.length as u64;
+        let out_of_range = || format!("条目 {} 超出数据区范围", entry.name);
+        match &self.storage {
+            Storage::Memory(data) => data
+                .get(start as usize..end as usize)
+                .map(Cow::Borrowed)
+                .ok_or_else(out_of_range),
+            Storage::Mapped(map) => map
+                .get(start as usize..end as usize)
+                .map(Cow::Borrowed)
+                .ok_or_else(out_of_range),
+            Storage::File(file) => {
+                let mut file = file
+                    .lock()
+                    .map_err(|_| format!("读取条目 {} 失败", entry.name))?;
+                let mut data = vec![0; entry.length as usize];
+                file.seek(SeekFrom::Start(start))
+                    .and_then(|_| file.read_exact(&mut data))
+                    .map_err(|e| format!("读取条目 {} 失败: {}", entry.name, e))?;
+                Ok(Cow::Owned(data))
+            }
+        }
+    }
+}
+
+// 解析头部与条目表，head 为文件开头（至少包含完整条目表），size 为文件总长度
+// 返回版本、条目与数据区起点
+fn parse_table(head: &[u8], size: usize) -> Result<(String, Vec<PkgEntry>, usize), ParseError> {
+    let mut reader = Reader::new(head);
+    let magic = reader.read_sized_string(MAX_MAGIC_LEN)?;
+    if !magic.starts_with("PKGV") {
+        return Err(ParseError::BadMagic {
+            expected: "pkg",
+            found: magic,
+        });
+    }
+    if !SUPPORTED_VERSIONS.contains(&magic.as_str()) {
+        return Err(ParseError::UnsupportedVersion {
+            what: "pkg",
+            version: magic,
+        });
+    }
+
+    let count = reader.read_u32()? as usize;
+    // 每个条目至少占 12 字节，先用剩余长度校验数量，避免异常数量导致过量分配
+    let max = size.saturating_sub(reader.position()) / 12;
+    if count > max {
+        return Err(ParseError::CountTooLarge {
+            what: "条目",
+            count: count as u64,
+            max: max as u64,
+        });
+    }
+    let mut entries = Vec::with_capacity(count.min(4096));
+    for _ in 0..count {
+        let name = reader.read_sized_string(MAX_NAME_LEN)?;
+        let offset = reader.read_u32()?;
+        let length = reader.read_u32()?;
+        entries.push(PkgEntry {
+            name,
+            offset,
+            length,
+        });
+    }
+
+    let data_start = reader.position();
+    let data_len = size.saturating_sub(data_start) as u64;
+    for entry in &entries {
+        let end = entry.offset as u64 + entry.length as u64;
+        if end > data_len {
+            return Err(ParseError::OutOfBounds {
+                name: entry.name.clone(),
+                offset: entry.offset as u64,
+                length: entry.length as u64,
+                size: data_len,
+            });
+        }
+    }
+
+    Ok((magic, entries, data_start))
+}
+
+// 将条目路径规范化为相对路径：/ 与 \ 均视为分隔符，去掉空段与 "."
+// 含 ".."、以分隔符开头（绝对路径、UNC）、盘符或 NTFS 数据流（含 :）时返回 None
+pub fn safe_entry_path(name: &str) -> Option<PathBuf> {
+    if name.starts_with(['/', '\\']) || name.contains('\0') {
+        return None;
+    }
+    let mut path = PathBuf::new();
+    for part in name.split(['/', '\\']) {
+        match part {
+            "" | "." => continue,
+            ".." => return None,
+            _ if part.contains(':') => return None,
+            _ => path.push(part),
+        }
+    }
+    // 再按当前平台的规则确认只剩普通路径段
+    if path.as_os_str().is_empty()
+        || !path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return None;
+    }
+    Some(path)
+}
+
+// 默认写出的 pkg 版本
+pub const DEFAULT_VERSION: &str = "PKGV0001";
+
+// 将 (条目路径, 内容) 列表写成 pkg 数据
+pub fn build(magic: &str, files: &[(String, Vec<u8>)]) -> Result<Vec<u8>, String> {
+    if !SUPPORTED_VERSIONS.contains(&magic) {
+        return Err(format!("无效的 pkg 版本 {:?}", magic));
+    }
+
+    let mut header = Vec::new();
+    write_sized_string(&mut header, magic);
+    header.extend_from_slice(&(files.len() as u32).to_le_bytes());
+
+    let mut offset: u64 = 0;
+    for (name, content) in files {
+        if name.is_empty() || name.len() > MAX_NAME_LEN {
+            return Err(format!("条目路径 {:?} 长度无效", name));
+        }
+        if offset + content.len() as u64 > u32::MAX as u64 {
+            return Err("pkg 数据超过 4GB".to_string());
+        }
+        write_sized_string(&mut header, name);
+        header.extend_from_slice(&(offset as u32).to_le_bytes());
+        header.extend_from_slice(&(content.len() as u32).to_le_bytes());
+        offset += content.len() as u64;
+    }
+
+    let mut data = header;
+    data.reserve(offset as usize);
+    for (_, content) in files {
+        data.extend_from_slice(content);
+    }
+    Ok(data)
+}
+
+fn write_sized_string(out: &mut Vec<u8>, value: &str) {
+    out.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    out.extend_from_slice(value.as_bytes());
+}
+
+// 将 dir 下的所有文件按相对路径（以 / 分隔）打包为 output，返回条目数量
+// 未指定版本时沿用 dir 中已有 scene.pkg 的版本，否则使用 DEFAULT_VERSION
+pub fn pack_dir(dir: &Path, output: &Path, version: Option<&str>) -> Result<usize, String> {
+    let magic = match version {
+        Some(version) => version.to_string(),
+        None => Pkg::open(&dir.join("scene.pkg"))
+            .map(|pkg| pkg.magic)
+            .unwrap_or_else(|_| DEFAULT_VERSION.to_string()),
+    };
+
+    let output_abs = output.canonicalize().ok();
+    let mut files = Vec::new();
+    for entry in WalkDir::new(dir).sort_by_file_name() {
+        let entry = entry.map_err(|e| format!("遍历 {:?} 失败: {}", dir, e))?;
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+        // 跳过输出文件自身与已有的 pkg
+        if path.extension().is_some_and(|e| e == "pkg")
+            || output_abs.is_some() && path.canonicalize().ok() == output_abs
+        {
+            continue;
+        }
+        let relative = path.strip_prefix(dir).map_err(|e| e.to_string())?;
+        let name = relative
+            .iter()
+            .map(|part| part.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let content = fs::read(path).map_err(|e| format!("读取 {:?} 失败: {}", path, e))?;
+        files.push((name, content));
+    }
+    if files.is_empty() {
+        return Err(format!("目录 {:?} 中没有可打包的文件", dir));
+    }
+
+    let data = build(&magic, &files)?;
+    fs::write(output, data).map_err(|e| format!("写入 {:?} 失败: {}", output, e))?;
+    Ok(files.len())
+}
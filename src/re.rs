@@ -1,11 +1,17 @@
-use crate::os;
-use std::ffi::OsStr;
-use std::fs::{self};
-use std::io;
-use std::path::{Path, PathBuf};
+use crate::alpha::{self, AlphaMode};
+use crate::anim::AnimFormat;
+use crate::classify::{Classifier, TextureRole};
+use crate::compose;
+use crate::graph::{self, ReferenceGraph};
+use crate::pkg;
+use crate::scene::Scene;
+use crate::source::{self, Package, Wallpaper};
+use crate::tex::{self, ConvertOptions, MipLevel, Tex};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
 
 const DEFAULT_SUFFIX: [&str; 3] = ["jpg", "png", "jpeg"];
-#[derive(Default)]
 pub struct Param {
     pub target: String,               // 指定目录
     pub saved: String,                // 保存目录
@@ -13,6 +19,52 @@ pub struct Param {
     pub all_combine: bool,            // 所有文件合并到一个文件夹
     pub cobo_status: usize,           // 0 "以文件夹分类"; 1 "合并到文件夹"; 2 "分类和合并"
     pub addition_suffix: Vec<String>, // 需要添加保存的后缀名称
+    pub anim_status: usize,           // 动图导出：0 精灵图; 1 GIF; 2 APNG; 3 WebP; 4 帧序列
+    pub keep_padding: bool,           // 保留纹理补齐区域
+    pub mip_status: usize,            // mipmap 层级：0 最大; 1 全部; 2 最接近指定尺寸
+    pub mip_size: u32,                // 指定尺寸（像素）
+    pub raw_dump: bool,               // 无法转换的纹理导出原始数据与头部信息
+    pub alpha_status: usize,          // 透明通道：0 保留; 1 填充底色; 2 反预乘
+    pub matte_color: [u8; 3],         // 填充底色（RGB）
+    pub artwork_only: bool,           // 只提取作品纹理，跳过遮罩、法线等辅助纹理
+    pub main_only: bool,              // 只导出场景的主背景图，以壁纸标题命名
+    pub compose: bool,                // 另外输出按 scene.json 合成的整张场景图
+    pub name_by_object: bool,         // 纹理按使用它的场景对象命名
+    pub skip_orphans: bool,           // 跳过没有被场景引用的纹理
+}
+
+impl Default for Param {
+    fn default() -> Self {
+        Param {
+            target: String::new(),
+            saved: String::new(),
+            as_title: false,
+            all_combine: false,
+            cobo_status: 0,
+            addition_suffix: Vec::new(),
+            anim_status: 0,
+            keep_padding: false,
+            mip_status: 0,
+            mip_size: tex::DEFAULT_MIP_SIZE,
+            raw_dump: false,
+            alpha_status: 0,
+            matte_color: [0, 0, 0],
+            artwork_only: false,
+            main_only: false,
+            compose: false,
+            name_by_object: false,
+            skip_orphans: false,
+        }
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct ExtractResult {
+    pub file_count: usize,      // 提取到的文件数量
+    pub errors: Vec<String>,    // 各壁纸、纹理的错误信息
+    pub versions: Vec<String>,  // 各壁纸检测到的 pkg 与 tex 版本
+    pub raw_dumps: Vec<String>, // 各壁纸中已原样导出的纹理
+    pub skipped: Vec<String>,   // 各壁纸中跳过的辅助纹理及判断依据
 }
 
 // 用于处理路径是否存在，并且是文件或目录
@@ -33,7 +85,7 @@ fn check_path(path: &str) -> Result<(), String> {
     }
 }
 
-pub fn extract(param: Param) -> Result<usize, String> {
+pub fn extract(param: Param) -> Result<ExtractResult, String> {
     let path = &param.target;
 
     // 检查 target 和 saved 路径
@@ -48,269 +100,418 @@ pub fn extract(param: Param) -> Result<usize, String> {
         return Err(format!("保存文件路径错误: {}", e));
     }
 
-    // 在saved下创建一个临时文件夹
-    // 拼接文件夹路径
-    let folder_path = Path::new(&param.saved).join("tmp");
-
-    // 使用 create_dir 创建文件夹
-    fs::create_dir(&folder_path).map_err(|e| format!("Error creating directory: {}", e))?;
-
-    // 拼接命令
-    let mut condition = "".to_string();
-    if param.as_title {
-        condition.push_str("-n ");
-    }
-    let result = format!(
-        "extract {}-o {} {}",
-        condition,
-        folder_path.to_str().unwrap(),
-        param.target
-    );
-
-    println!("{}", result);
+    let mut result = ExtractResult::default();
+    let options = ConvertOptions {
+        anim_format: AnimFormat::from_status(param.anim_status),
+        keep_padding: param.keep_padding,
+        mip_level: MipLevel::from_status(param.mip_status, param.mip_size),
+        alpha: AlphaMode::from_status(param.alpha_status, param.matte_color),
+    };
 
-    os::process_repkg(&result);
+    // 非纹理条目中需要保存的后缀
     let mut extensions = DEFAULT_SUFFIX.map(|s| s.to_string()).to_vec();
     // 添加指定后缀
-    for ele in param.addition_suffix.into_iter() {
+    for ele in param.addition_suffix.iter() {
         if !ele.is_empty() {
-            extensions.push(ele);
+            extensions.push(ele.clone());
         }
     }
-    let files = search_files_with_extension(&folder_path.to_string_lossy(), &extensions);
 
-    let target_directory = &param.saved;
-    let file_len = files.len();
-    if files.is_empty() {
+    // 来源可以是壁纸目录、pkg 或 zip；直接在内存中读取条目并转换纹理，结果写入最终位置
+    let source = source::open(Path::new(path))?;
+    for wallpaper in source.wallpapers() {
+        let name = wallpaper_name(&wallpaper, param.as_title);
+        let targets = output_targets(Path::new(save), &name, param.cobo_status);
+        let packages = match source.open(&wallpaper) {
+            Ok(packages) => packages,
+            Err(e) => {
+                eprintln!("解包 {} 失败: {}", wallpaper.path, e);
+                result.errors.push(e);
+                continue;
+            }
+        };
+        if param.compose {
+            if let Err(e) = compose_scene(&packages, &targets, &mut result) {
+                eprintln!("{}: {}", name, e);
+                result.errors.push(format!("{}: 合成场景失败: {}", name, e));
+            }
+        }
+        if param.main_only {
+            let title = wallpaper_name(&wallpaper, true);
+            let mut tex_versions = BTreeMap::new();
+            if let Err(e) = extract_main_image(
+                &packages,
+                &title,
+                &targets,
+                &options,
+                &mut tex_versions,
+                &mut result,
+            ) {
+                eprintln!("{}: {}", name, e);
+                result.errors.push(format!("{}: {}", name, e));
+            }
+            result.versions.push(version_line(
+                &name,
+                &package_versions(&packages),
+                &tex_versions,
+            ));
+            continue;
+        }
+        extract_wallpaper(
+            &packages,
+            &name,
+            &targets,
+            &extensions,
+            &options,
+            &param,
+            &mut result,
+        );
+    }
+
+    if result.file_count == 0 {
         println!(
             "No files found with the specified extensions in the directory {}",
             path
         );
-    } else {
-        println!("Found {} files. Moving them...", file_len);
-        println!("tmp save: {}", folder_path.to_str().unwrap());
-        // 将文件移动到目标目录
-        if let Err(e) = move_files_to_directory(
-            files,
-            folder_path.to_str().unwrap(),
-            target_directory,
-            param.cobo_status,
-        ) {
-            eprintln!("Error moving files: {}", e);
-        }
     }
-
-    // 结束前，删除临时目录
-    fs::remove_dir_all(folder_path)
-        .map_err(|e| format!("Failed to delete temporary convert file: {}", e))?;
-    let temp_exe_path = Path::new("RePKG_temp.exe");
-    fs::remove_file(temp_exe_path)
-        .map_err(|e| format!("Failed to delete temporary EXE file: {}", e))?;
-    return Ok(file_len);
+    Ok(result)
 }
 
-fn search_files_with_extension(directory: &str, extensions: &[String]) -> Vec<PathBuf> {
-    let mut result = Vec::new();
+// 按“提取文件保存”选项给出输出目录及文件名前缀：
+// 0 保存到 <saved>/<壁纸名称>; 1 保存到 <saved>/pics，文件名为 壁纸名称-原文件名; 2 两者都保存（汇总目录为 AAA-pics）
+fn output_targets(saved: &Path, name: &str, cobo_status: usize) -> Vec<(PathBuf, String)> {
+    let own = (saved.join(name), String::new());
+    let combined = |folder: &str| (saved.join(folder), format!("{}-", name));
+    match cobo_status {
+        1 => vec![combined("pics")],
+        2 => vec![own, combined("AAA-pics")],
+        _ => vec![own],
+    }
+}
 
-    // 递归读取目录内容
-    if let Ok(entries) = fs::read_dir(directory) {
-        for entry in entries {
-            match entry {
-                Ok(entry) => {
-                    let path = entry.path();
+// 逐个读取壁纸各组文件的条目：tex 在内存中转换，其余符合后缀的文件原样输出
+// 路径不安全的条目、转换失败的纹理以 壁纸名称/条目路径 记录到 result.errors
+// raw_dump 时转换失败的纹理另外导出 .raw 与 .raw.json，按壁纸汇总到 result.raw_dumps
+// artwork_only 时跳过辅助纹理，skip_orphans 时跳过未被场景引用的纹理，按壁纸汇总到 result.skipped
+// name_by_object 时纹理以使用它的场景对象命名
+fn extract_wallpaper(
+    packages: &[Package],
+    name: &str,
+    targets: &[(PathBuf, String)],
+    extensions: &[String],
+    options: &ConvertOptions,
+    param: &Param,
+    result: &mut ExtractResult,
+) {
+    let mut tex_versions = BTreeMap::new();
+    let mut dumped = Vec::new();
+    let mut skipped = Vec::new();
+    let classifier = if param.artwork_only {
+        Some(Classifier::from_packages(packages))
+    } else {
+        None
+    };
+    let graph = if param.name_by_object || param.skip_orphans {
+        Scene::load(packages)
+            .ok()
+            .flatten()
+            .map(|scene| graph::build(&scene, packages))
+    } else {
+        None
+    };
+    let stems = match &graph {
+        Some(graph) if param.name_by_object => object_stems(graph),
+        _ => HashMap::new(),
+    };
+    let mut orphans = Vec::new();
+    for package in packages {
+        println!("{}: 共 {} 个条目", package.path, package.entries.len());
+        for (index, entry) in package.entries.iter().enumerate() {
+            let file_name = match pkg::safe_entry_path(&entry.name)
+                .and_then(|path| path.file_name().map(|n| n.to_string_lossy().into_owned()))
+            {
+                Some(file_name) => file_name,
+                None => {
+                    result.errors.push(format!(
+                        "{}: 条目 {:?} 路径不安全，已跳过",
+                        package.path, entry.name
+                    ));
+                    continue;
+                }
+            };
+            // 只读取需要输出的条目
+            let is_tex = file_name.ends_with(".tex");
+            if !is_tex && !has_extension(&file_name, extensions) {
+                continue;
+            }
+            if is_tex
+                && param.skip_orphans
+                && graph.as_ref().is_some_and(|g| g.is_orphan(&entry.name))
+            {
+                orphans.push(entry.name.clone());
+                continue;
+            }
+            let data = match package.read(index) {
+                Ok(data) => data,
+                Err(e) => {
+                    result
+                        .errors
+                        .push(format!("{}/{}: {}", name, entry.name, e));
+                    continue;
+                }
+            };
+            if !is_tex {
+                // 直接输出的 png 与纹理转换结果使用相同的透明通道处理
+                match alpha::apply_to_file(&file_name, &data, options.alpha) {
+                    Ok(content) => write_output(targets, &file_name, &content, result),
+                    Err(e) => result
+                        .errors
+                        .push(format!("{}/{}: {}", name, entry.name, e)),
+                }
+                continue;
+            }
 
-                    // 如果是文件并且具有指定的扩展名
-                    if path.is_file() {
-                        if let Some(ext) = path.extension() {
-                            if extensions.iter().any(|e| ext == e.as_str()) {
-                                result.push(path);
-                            }
-                        }
+            count_version(&mut tex_versions, &data);
+            let stem = stems
+                .get(&entry.name.to_ascii_lowercase())
+                .map_or(file_name.trim_end_matches(".tex"), String::as_str);
+            let converted = Tex::parse(&data).map_err(String::from).and_then(|tex| {
+                if let Some(classifier) = &classifier {
+                    let (role, reason) = classifier.classify(&entry.name, &tex);
+                    if role == TextureRole::Auxiliary {
+                        skipped.push(format!("{}（{}）", entry.name, reason));
+                        return Ok(Vec::new());
                     }
-                    // 如果是目录，则递归搜索
-                    else if path.is_dir() {
-                        result.extend(search_files_with_extension(
-                            path.to_str().unwrap(),
-                            &extensions,
-                        ));
+                }
+                tex::convert_tex(&tex, stem, options)
+            });
+            match converted {
+                Ok(outputs) => {
+                    for (output, content) in outputs {
+                        write_output(targets, &output, &content, result);
                     }
                 }
                 Err(e) => {
-                    eprintln!("Failed to read entry: {}", e);
+                    eprintln!("转换 {}/{} 失败: {}", name, entry.name, e);
+                    result
+                        .errors
+                        .push(format!("{}/{}: {}", name, entry.name, e));
+                    if param.raw_dump {
+                        match tex::raw_dump(&data, stem, &e) {
+                            Ok(outputs) => {
+                                for (output, content) in outputs {
+                                    write_output(targets, &output, &content, result);
+                                }
+                                dumped.push(entry.name.clone());
+                            }
+                            Err(e) => result
+                                .errors
+                                .push(format!("{}/{}: 导出原始数据失败: {}", name, entry.name, e)),
+                        }
+                    }
                 }
             }
         }
-    } else {
-        eprintln!("Failed to read directory: {}", directory);
     }
-
-    result
-}
-
-fn move_files_to_directory(
-    files: Vec<PathBuf>,
-    source_directory: &str,
-    target_root_directory: &str,
-    combo: usize,
-) -> io::Result<()> {
-    match combo {
-        0 => move_files_for_directory(files, source_directory, target_root_directory),
-        1 => move_files_com_directory(files, source_directory, target_root_directory),
-        2 => move_files_all_directory(files, source_directory, target_root_directory),
-        _ => move_files_for_directory(files, source_directory, target_root_directory),
+    if !orphans.is_empty() {
+        result.skipped.push(format!(
+            "{}: 跳过 {} 个未被场景引用的纹理：{}",
+            name,
+            orphans.len(),
+            orphans.join("、")
+        ));
+    }
+    if !skipped.is_empty() {
+        result.skipped.push(format!(
+            "{}: 跳过 {} 个辅助纹理：{}",
+            name,
+            skipped.len(),
+            skipped.join("、")
+        ));
     }
+    if !dumped.is_empty() {
+        result
+            .raw_dumps
+            .push(format!("{}: {}", name, dumped.join("、")));
+    }
+    result.versions.push(version_line(
+        name,
+        &package_versions(packages),
+        &tex_versions,
+    ));
 }
 
-fn move_files_for_directory(
-    files: Vec<PathBuf>,
-    source_directory: &str,
-    target_root_directory: &str,
-) -> io::Result<()> {
-    // 遍历所有找到的文件
-    for file in files {
-        // 获取文件的父目录路径，去除源目录的前缀，以便保留目录结构
-        let relative_path = match file.strip_prefix(source_directory) {
-            Ok(rp) => rp,
-            Err(e) => {
-                eprintln!("Failed to strip prefix from {:?}: {}", file, e);
-                continue; // 跳过当前文件，继续处理下一个文件
-            }
-        };
-        let first_folder = relative_path
-            .iter()
-            .next()
-            .unwrap_or_else(|| OsStr::new(""));
-
-        println!("parent dir: {}", first_folder.to_string_lossy());
-        // 拼接目标目录结构
-        let target_directory = Path::new(target_root_directory).join(first_folder);
-        println!("target dir: {}", target_directory.to_string_lossy());
-        // 确保目标目录存在
-        fs::create_dir_all(&target_directory)?;
-
-        // 目标文件的完整路径
-        let target_path = target_directory.join(file.file_name().unwrap());
-
-        println!("new file path: {}", target_path.to_string_lossy());
-        // 执行文件移动
-        match fs::rename(&file, &target_path) {
-            Ok(_) => println!("Successfully moved: {:?} to {:?}", file, target_path),
-            Err(e) => eprintln!("Failed to move {:?} to {:?}: {}", file, target_path, e),
-        }
+// 按 scene.json 找到主背景图层，只转换其纹理，输出文件以壁纸标题命名
+fn extract_main_image(
+    packages: &[Package],
+    title: &str,
+    targets: &[(PathBuf, String)],
+    options: &ConvertOptions,
+    tex_versions: &mut BTreeMap<String, usize>,
+    result: &mut ExtractResult,
+) -> Result<(), String> {
+    let scene =
+        Scene::load(packages)?.ok_or_else(|| "不是场景壁纸，没有 scene.json".to_string())?;
+    let layer = scene
+        .main_layer()
+        .ok_or_else(|| "scene.json 中没有可见的图像图层".to_string())?;
+    let texture = layer
+        .image()
+        .and_then(|image| image.texture())
+        .unwrap_or_default();
+    let entry = format!("materials/{}.tex", texture);
+    println!("{}: 主图层 {:?}，纹理 {}", title, layer.name, entry);
+    let (package, index) = source::find_entry(packages, &entry)
+        .ok_or_else(|| format!("找不到主图层纹理 {}", entry))?;
+    let data = package.read(index)?;
+    count_version(tex_versions, &data);
+    for (output, content) in tex::convert(&data, title, options)? {
+        write_output(targets, &output, &content, result);
     }
+    Ok(())
+}
 
+// 合成图以 scene_composite.png 输出到壁纸的输出目录
+fn compose_scene(
+    packages: &[Package],
+    targets: &[(PathBuf, String)],
+    result: &mut ExtractResult,
+) -> Result<(), String> {
+    let scene =
+        Scene::load(packages)?.ok_or_else(|| "不是场景壁纸，没有 scene.json".to_string())?;
+    let image = compose::compose(&scene, packages)?;
+    write_output(
+        targets,
+        "scene_composite.png",
+        &tex::encode_png(&image)?,
+        result,
+    );
     Ok(())
 }
 
-fn move_files_com_directory(
-    files: Vec<PathBuf>,
-    source_directory: &str,
-    target_root_directory: &str,
-) -> io::Result<()> {
-    // 遍历所有找到的文件
-    for file in files {
-        // 获取文件的父目录路径，去除源目录的前缀，以便保留目录结构
-        let relative_path = match file.strip_prefix(source_directory) {
-            Ok(rp) => rp,
-            Err(e) => {
-                eprintln!("Failed to strip prefix from {:?}: {}", file, e);
-                continue; // 跳过当前文件，继续处理下一个文件
-            }
+// 纹理条目（小写）到输出文件名的映射：作为图层图片时为 对象名，其他用途为 对象名_原文件名，重名时追加序号
+fn object_stems(graph: &ReferenceGraph) -> HashMap<String, String> {
+    let mut stems = HashMap::new();
+    let mut used = HashSet::new();
+    for texture in &graph.textures {
+        let Some(user) = graph.primary_user(&texture.entry) else {
+            continue;
         };
-
-        let first_folder = relative_path
-            .iter()
+        let object = sanitize_file_name(user.name.trim());
+        if object.is_empty() {
+            continue;
+        }
+        let original = texture
+            .entry
+            .rsplit('/')
             .next()
-            .unwrap_or_else(|| OsStr::new(""));
-
-        println!("parent dir: {}", first_folder.to_string_lossy());
-        // 拼接目标目录结构
-        let target_directory = Path::new(target_root_directory).join("pics");
-        println!("target dir: {}", target_directory.to_string_lossy());
-        // 确保目标目录存在
-        fs::create_dir_all(&target_directory)?;
-
-        // 获取文件名，并将文件名前加上 parent_folder
-        let new_file_name = format!(
-            "{}-{}",
-            first_folder.to_string_lossy(),
-            file.file_name().unwrap().to_string_lossy()
-        );
-
-        // 目标文件的完整路径
-        let target_path = target_directory.join(new_file_name);
-
-        println!("new file path: {}", target_path.to_string_lossy());
-        // 执行文件移动
-        match fs::rename(&file, &target_path) {
-            Ok(_) => println!("Successfully moved: {:?} to {:?}", file, target_path),
-            Err(e) => eprintln!("Failed to move {:?} to {:?}: {}", file, target_path, e),
+            .unwrap_or_default()
+            .trim_end_matches(".tex");
+        let base = if user.main {
+            object
+        } else {
+            format!("{}_{}", object, original)
+        };
+        let mut stem = base.clone();
+        let mut index = 2;
+        while !used.insert(stem.to_lowercase()) {
+            stem = format!("{}_{}", base, index);
+            index += 1;
         }
+        stems.insert(texture.entry.to_ascii_lowercase(), stem);
     }
+    stems
+}
 
-    Ok(())
+// 按文件名后缀匹配，支持 frames.json 这类多段后缀
+fn has_extension(name: &str, extensions: &[String]) -> bool {
+    extensions
+        .iter()
+        .any(|e| name.ends_with(&format!(".{}", e)))
 }
 
-fn move_files_all_directory(
-    files: Vec<PathBuf>,
-    source_directory: &str,
-    target_root_directory: &str,
-) -> io::Result<()> {
-    // 遍历所有找到的文件
-    for file in files {
-        // 获取文件的父目录路径，去除源目录的前缀，以便保留目录结构
-        let relative_path = match file.strip_prefix(source_directory) {
-            Ok(rp) => rp,
-            Err(e) => {
-                eprintln!("Failed to strip prefix from {:?}: {}", file, e);
-                continue; // 跳过当前文件，继续处理下一个文件
-            }
-        };
-        let first_folder = relative_path
-            .iter()
-            .next()
-            .unwrap_or_else(|| OsStr::new(""));
-        // 拼接目标目录结构
-        let target_directory = Path::new(target_root_directory).join(first_folder);
-        // 确保目标目录存在
-        fs::create_dir_all(&target_directory)?;
-        // 目标文件的完整路径
-        let target_path = target_directory.join(file.file_name().unwrap());
-        match fs::copy(&file, &target_path) {
-            Ok(_) => println!(
-                "Successfully copied to first folder: {:?} -> {:?}",
-                file, target_path
-            ),
-            Err(e) => eprintln!(
-                "Failed to copy to first folder: {:?} -> {:?}: {}",
-                file, target_path, e
-            ),
+// 将一个输出文件写入所有目标目录，计为一个提取到的文件
+fn write_output(
+    targets: &[(PathBuf, String)],
+    file_name: &str,
+    data: &[u8],
+    result: &mut ExtractResult,
+) {
+    for (dir, prefix) in targets {
+        let target = dir.join(format!("{}{}", prefix, file_name));
+        if let Err(e) = fs::create_dir_all(dir).and_then(|_| fs::write(&target, data)) {
+            eprintln!("写入 {:?} 失败: {}", target, e);
+            result
+                .errors
+                .push(format!("写入 {} 失败: {}", target.display(), e));
+            return;
         }
-        println!("new file path: {}", target_path.to_string_lossy());
+        println!("new file path: {}", target.to_string_lossy());
+    }
+    result.file_count += 1;
+}
 
-        // 拼接目标目录结构
-        let target_directory = Path::new(target_root_directory).join("AAA-pics");
-        println!("target dir: {}", target_directory.to_string_lossy());
-        // 确保目标目录存在
-        fs::create_dir_all(&target_directory)?;
+// 统计 tex 版本，无法识别时记为“未知”
+fn count_version(tex_versions: &mut BTreeMap<String, usize>, data: &[u8]) {
+    let version = tex::detect_versions(data).unwrap_or_else(|_| "未知".to_string());
+    *tex_versions.entry(version).or_insert(0) += 1;
+}
 
-        // 获取文件名，并将文件名前加上 parent_folder
-        let new_file_name = format!(
-            "{}-{}",
-            first_folder.to_string_lossy(),
-            file.file_name().unwrap().to_string_lossy()
-        );
+fn package_versions(packages: &[Package]) -> Vec<&str> {
+    packages
+        .iter()
+        .filter_map(|package| package.version.as_deref())
+        .collect()
+}
 
-        // 目标文件的完整路径
-        let target_path = target_directory.join(new_file_name);
+// 版本摘要，如 "名称: PKGV0019；TEXV0005/TEXI0001/TEXB0003 ×4"
+// 没有 pkg 的壁纸记为“散装文件”
+fn version_line(name: &str, pkgs: &[&str], textures: &BTreeMap<String, usize>) -> String {
+    let textures = textures
+        .iter()
+        .map(|(version, count)| format!("{} ×{}", version, count))
+        .collect::<Vec<_>>()
+        .join("，");
+    let pkgs = if pkgs.is_empty() {
+        "散装文件".to_string()
+    } else {
+        pkgs.join("、")
+    };
+    format!("{}: {}；{}", name, pkgs, textures)
+}
 
-        println!("new file path: {}", target_path.to_string_lossy());
-        // 执行文件移动
-        match fs::rename(&file, &target_path) {
-            Ok(_) => println!("Successfully moved: {:?} to {:?}", file, target_path),
-            Err(e) => eprintln!("Failed to move {:?} to {:?}: {}", file, target_path, e),
-        }
+// 壁纸输出目录名：勾选“以名称创建文件夹”时使用 project.json 中的 title，
+// 标题清理后为空（如 ".."）时退回壁纸目录名
+fn wallpaper_name(wallpaper: &Wallpaper, as_title: bool) -> String {
+    let title = match &wallpaper.title {
+        Some(title) if as_title => sanitize_file_name(title),
+        _ => String::new(),
+    };
+    if !title.is_empty() {
+        return title;
+    }
+    match sanitize_file_name(&wallpaper.name) {
+        name if name.is_empty() => "_".to_string(),
+        name => name,
     }
+}
 
-    Ok(())
+// 替换 Windows 文件名中不允许出现的字符，并去掉首尾空格与结尾的点；
+// 名称来自 project.json 或 scene.json，不可信，结果不是单个普通路径分量（如 "."、".."）时返回空字符串
+fn sanitize_file_name(name: &str) -> String {
+    let name: String = name
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '\\' | '/' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let name = name.trim_end_matches(['.', ' ']);
+    let mut components = Path::new(name).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(_)), None) => name.to_string(),
+        _ => String::new(),
+    }
 }
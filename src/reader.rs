@@ -0,0 +1,73 @@
+use crate::error::ParseError;
+
+// 小端二进制读取工具，pkg / tex 解析共用，所有读取均做边界检查
+pub(crate) struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Reader { buf, pos: 0 }
+    }
+
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.buf.len() - self.pos
+    }
+
+    pub fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], ParseError> {
+        if len > self.remaining() {
+            return Err(ParseError::UnexpectedEof {
+                offset: self.pos,
+                needed: len,
+                remaining: self.remaining(),
+            });
+        }
+        let bytes = &self.buf[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, ParseError> {
+        let bytes = self.read_bytes(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    pub fn read_i32(&mut self) -> Result<i32, ParseError> {
+        Ok(self.read_u32()? as i32)
+    }
+
+    pub fn read_f32(&mut self) -> Result<f32, ParseError> {
+        Ok(f32::from_bits(self.read_u32()?))
+    }
+
+    // 以 int32 长度为前缀的字符串（PKG 头部与条目名称使用）
+    pub fn read_sized_string(&mut self, max_len: usize) -> Result<String, ParseError> {
+        let len = self.read_u32()? as usize;
+        if len > max_len {
+            return Err(ParseError::StringTooLong { len, max: max_len });
+        }
+        let bytes = self.read_bytes(len)?;
+        Ok(String::from_utf8_lossy(bytes).into_owned())
+    }
+
+    // 以 \0 结尾的字符串（TEX 各段头部使用），最多读取 max_len 字节
+    pub fn read_null_string(&mut self, max_len: usize) -> Result<String, ParseError> {
+        let mut bytes = Vec::new();
+        loop {
+            let byte = self.read_bytes(1)?[0];
+            if byte == 0 {
+                break;
+            }
+            bytes.push(byte);
+            if bytes.len() >= max_len {
+                break;
+            }
+        }
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+}
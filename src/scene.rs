@@ -0,0 +1,466 @@
+use crate::source::{self, Package};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+// scene.json 的类型化模型：相机、通用设置（正交投影尺寸即场景画布）、各对象及其材质引用。
+// scene.json 中的很多值可以绑定用户属性，写作 {"user": "...", "value": ...}，解析时取其中的 value；
+// 向量写作 "x y z" 字符串。缺失或无法识别的字段取默认值，不影响其余字段
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct Scene {
+    pub version: Option<u64>,
+    pub camera: Camera,
+    pub general: General,
+    pub objects: Vec<SceneObject>,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct Camera {
+    pub center: [f32; 3],
+    pub eye: [f32; 3],
+    pub up: [f32; 3],
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct General {
+    pub orthogonal_projection: Option<Projection>, // 正交投影，透视场景为 None
+    pub clear_color: [f32; 3],                     // 背景色
+    pub ambient_color: [f32; 3],
+    pub zoom: f32,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct Projection {
+    pub width: f32,
+    pub height: f32,
+    pub auto: bool, // 自动适配屏幕，此时没有固定尺寸
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct SceneObject {
+    pub id: i64,
+    pub name: String,
+    pub parent: Option<i64>, // 父对象 id，坐标相对父对象
+    pub origin: [f32; 3],    // 中心位置（场景像素坐标，y 向上）
+    pub scale: [f32; 3],
+    pub angles: [f32; 3], // 旋转角度（弧度）
+    pub visible: bool,
+    pub kind: ObjectKind,
+    pub effects: Vec<Effect>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ObjectKind {
+    Image(ImageLayer),
+    Particle { file: String },
+    Sound { files: Vec<String> },
+    Text,
+    Light,
+    Other,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ImageLayer {
+    pub model: String,              // 模型 json，如 models/bg.json
+    pub size: Option<[f32; 2]>,     // 图层尺寸，未写明时取模型中的尺寸
+    pub alpha: f32,                 // 不透明度
+    pub color: [f32; 3],            // 颜色叠加
+    pub material: Option<Material>, // 由模型引用的材质，load 时解析
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Material {
+    pub path: String, // 材质 json，如 materials/bg.json
+    pub shader: String,
+    pub blending: String,
+    pub textures: Vec<Option<String>>, // 第一个 pass 的纹理槽位，引用 materials/ 下不带扩展名的 tex
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Effect {
+    pub file: String, // 特效 json，如 effects/shake/effect.json
+    pub name: String,
+    pub visible: bool,
+    pub textures: Vec<Vec<Option<String>>>, // 各 pass 覆盖的纹理槽位
+}
+
+impl Scene {
+    pub fn parse(data: &[u8]) -> Result<Scene, String> {
+        let json: Value =
+            serde_json::from_slice(data).map_err(|e| format!("解析 scene.json 失败: {}", e))?;
+        let root = json
+            .as_object()
+            .ok_or_else(|| "scene.json 不是对象".to_string())?;
+
+        let camera = root.get("camera").map(value).unwrap_or(&Value::Null);
+        let general = root.get("general").map(value).unwrap_or(&Value::Null);
+        let objects = root
+            .get("objects")
+            .and_then(Value::as_array)
+            .map(|objects| objects.iter().filter_map(parse_object).collect())
+            .unwrap_or_default();
+        Ok(Scene {
+            version: root.get("version").and_then(Value::as_u64),
+            camera: Camera {
+                center: vec3(camera.get("center"), [0.0; 3]),
+                eye: vec3(camera.get("eye"), [0.0; 3]),
+                up: vec3(camera.get("up"), [0.0, 1.0, 0.0]),
+            },
+            general: General {
+                orthogonal_projection: parse_projection(general.get("orthogonalprojection")),
+                clear_color: vec3(general.get("clearcolor"), [0.0; 3]),
+                ambient_color: vec3(general.get("ambientcolor"), [0.0; 3]),
+                zoom: number(general.get("zoom"), 1.0),
+            },
+            objects,
+        })
+    }
+
+    // 从壁纸的各组文件中读取 scene.json，并解析图像图层引用的模型与材质；
+    // 没有 scene.json（视频、网页壁纸）时返回 None
+    pub fn load(packages: &[Package]) -> Result<Option<Scene>, String> {
+        let Some(data) = read_entry(packages, "scene.json") else {
+            return Ok(None);
+        };
+        let mut scene = Scene::parse(&data?)?;
+        for object in &mut scene.objects {
+            if let ObjectKind::Image(layer) = &mut object.kind {
+                resolve_model(packages, layer);
+            }
+        }
+        Ok(Some(scene))
+    }
+
+    // 固定尺寸的正交投影，即场景画布大小
+    pub fn canvas_size(&self) -> Option<(f32, f32)> {
+        match self.general.orthogonal_projection {
+            Some(projection) if !projection.auto && projection.width > 0.0 => {
+                Some((projection.width, projection.height))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn object(&self, id: i64) -> Option<&SceneObject> {
+        self.objects.iter().find(|object| object.id == id)
+    }
+
+    // 所有图像图层，按 scene.json 中的顺序（即绘制顺序，后绘制的在上层）
+    pub fn image_layers(&self) -> impl Iterator<Item = (&SceneObject, &ImageLayer)> {
+        self.objects
+            .iter()
+            .filter_map(|object| object.image().map(|layer| (object, layer)))
+    }
+
+    // 对象及其所有父对象均可见
+    pub fn is_visible(&self, object: &SceneObject) -> bool {
+        let mut current = Some(object);
+        // 防止父对象成环
+        for _ in 0..=self.objects.len() {
+            match current {
+                Some(object) if !object.visible => return false,
+                Some(object) => current = object.parent.and_then(|id| self.object(id)),
+                None => return true,
+            }
+        }
+        true
+    }
+
+    // 世界坐标中的中心位置：逐级加上父对象的位置（忽略父对象的缩放与旋转）
+    pub fn world_origin(&self, object: &SceneObject) -> [f32; 3] {
+        let mut origin = object.origin;
+        let mut parent = object.parent;
+        for _ in 0..self.objects.len() {
+            let Some(current) = parent.and_then(|id| self.object(id)) else {
+                break;
+            };
+            for (value, offset) in origin.iter_mut().zip(current.origin) {
+                *value += offset;
+            }
+            parent = current.parent;
+        }
+        origin
+    }
+
+    // 图层在场景中的外接矩形 (左, 下, 右, 上)，按缩放与 z 轴旋转计算
+    pub fn layer_bounds(&self, object: &SceneObject) -> Option<[f32; 4]> {
+        let size = object.image()?.size?;
+        let [x, y, _] = self.world_origin(object);
+        let width = size[0] * object.scale[0].abs();
+        let height = size[1] * object.scale[1].abs();
+        let (sin, cos) = object.angles[2].sin_cos();
+        let half_width = (width * cos.abs() + height * sin.abs()) / 2.0;
+        let half_height = (width * sin.abs() + height * cos.abs()) / 2.0;
+        Some([
+            x - half_width,
+            y - half_height,
+            x + half_width,
+            y + half_height,
+        ])
+    }
+
+    // 主背景图层：可见且有纹理的图像图层中覆盖画布面积最大的一个，
+    // 覆盖面积相同时取自身面积更大的，再相同时取先绘制的（更靠底层）；没有固定画布时按自身面积比较
+    pub fn main_layer(&self) -> Option<&SceneObject> {
+        let canvas = self
+            .canvas_size()
+            .map(|(width, height)| [0.0, 0.0, width, height]);
+        let mut best: Option<(&SceneObject, f32, f32)> = None;
+        for (object, layer) in self.image_layers() {
+            if !self.is_visible(object) || layer.alpha <= 0.0 || layer.texture().is_none() {
+                continue;
+            }
+            let Some(bounds) = self.layer_bounds(object) else {
+                continue;
+            };
+            let own = area(bounds);
+            let covered = canvas.map_or(own, |canvas| area(intersect(bounds, canvas)));
+            let better = match best {
+                Some((_, best_covered, best_area)) => {
+                    covered > best_covered || (covered == best_covered && own > best_area)
+                }
+                None => true,
+            };
+            if better {
+                best = Some((object, covered, own));
+            }
+        }
+        best.map(|(object, _, _)| object)
+    }
+}
+
+impl SceneObject {
+    pub fn image(&self) -> Option<&ImageLayer> {
+        match &self.kind {
+            ObjectKind::Image(layer) => Some(layer),
+            _ => None,
+        }
+    }
+}
+
+impl ImageLayer {
+    // 材质第一个槽位的纹理，即图层显示的图片
+    pub fn texture(&self) -> Option<&str> {
+        self.material
+            .as_ref()
+            .and_then(|material| material.textures.first())
+            .and_then(|texture| texture.as_deref())
+    }
+}
+
+impl Material {
+    pub fn parse(path: &str, data: &[u8]) -> Result<Material, String> {
+        let json: Value =
+            serde_json::from_slice(data).map_err(|e| format!("解析 {} 失败: {}", path, e))?;
+        let pass = json
+            .get("passes")
+            .and_then(Value::as_array)
+            .and_then(|passes| passes.first())
+            .unwrap_or(&Value::Null);
+        Ok(Material {
+            path: path.to_string(),
+            shader: string(pass.get("shader")).unwrap_or_default(),
+            blending: string(pass.get("blending")).unwrap_or_default(),
+            textures: textures(pass.get("textures")),
+        })
+    }
+}
+
+fn read_entry(packages: &[Package], name: &str) -> Option<Result<Vec<u8>, String>> {
+    let (package, index) = source::find_entry(packages, name)?;
+    Some(package.read(index).map(|data| data.into_owned()))
+}
+
+// 模型 json 给出材质路径与默认尺寸；模型或材质缺失时保留 None
+fn resolve_model(packages: &[Package], layer: &mut ImageLayer) {
+    let Some(Ok(data)) = read_entry(packages, &layer.model) else {
+        return;
+    };
+    let Ok(model) = serde_json::from_slice::<Value>(&data) else {
+        return;
+    };
+    if layer.size.is_none() {
+        let width = number(model.get("width"), 0.0);
+        let height = number(model.get("height"), 0.0);
+        if width > 0.0 && height > 0.0 {
+            layer.size = Some([width, height]);
+        }
+    }
+    let Some(path) = string(model.get("material")) else {
+        return;
+    };
+    if let Some(Ok(data)) = read_entry(packages, &path) {
+        layer.material = Material::parse(&path, &data).ok();
+    }
+}
+
+fn parse_object(value: &Value) -> Option<SceneObject> {
+    let object = value.as_object()?;
+    Some(SceneObject {
+        id: object.get("id").and_then(Value::as_i64).unwrap_or(-1),
+        name: string(object.get("name")).unwrap_or_default(),
+        parent: object.get("parent").and_then(Value::as_i64),
+        origin: vec3(object.get("origin"), [0.0; 3]),
+        scale: vec3(object.get("scale"), [1.0; 3]),
+        angles: vec3(object.get("angles"), [0.0; 3]),
+        visible: boolean(object.get("visible"), true),
+        kind: parse_kind(object),
+        effects: object
+            .get("effects")
+            .and_then(Value::as_array)
+            .map(|effects| effects.iter().filter_map(parse_effect).collect())
+            .unwrap_or_default(),
+    })
+}
+
+fn parse_kind(object: &Map<String, Value>) -> ObjectKind {
+    if let Some(model) = string(object.get("image")) {
+        let size = object
+            .get("size")
+            .map(|size| vec2(Some(size), [0.0; 2]))
+            .filter(|size| size[0] > 0.0 && size[1] > 0.0);
+        return ObjectKind::Image(ImageLayer {
+            model,
+            size,
+            alpha: number(object.get("alpha"), 1.0),
+            color: vec3(object.get("color"), [1.0; 3]),
+            material: None,
+        });
+    }
+    if let Some(file) = string(object.get("particle")) {
+        return ObjectKind::Particle { file };
+    }
+    if let Some(sound) = object.get("sound") {
+        let files = match value(sound) {
+            Value::Array(files) => files.iter().filter_map(|f| string(Some(f))).collect(),
+            other => string(Some(other)).into_iter().collect(),
+        };
+        return ObjectKind::Sound { files };
+    }
+    if object.contains_key("text") {
+        return ObjectKind::Text;
+    }
+    if object.contains_key("light") {
+        return ObjectKind::Light;
+    }
+    ObjectKind::Other
+}
+
+fn parse_effect(value: &Value) -> Option<Effect> {
+    let file = string(value.get("file"))?;
+    Some(Effect {
+        name: string(value.get("name")).unwrap_or_default(),
+        visible: boolean(value.get("visible"), true),
+        textures: value
+            .get("passes")
+            .and_then(Value::as_array)
+            .map(|passes| {
+                passes
+                    .iter()
+                    .map(|pass| textures(pass.get("textures")))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        file,
+    })
+}
+
+fn parse_projection(value: Option<&Value>) -> Option<Projection> {
+    let value = self::value(value?);
+    if !value.is_object() {
+        return None;
+    }
+    Some(Projection {
+        width: number(value.get("width"), 0.0),
+        height: number(value.get("height"), 0.0),
+        auto: boolean(value.get("auto"), false),
+    })
+}
+
+// 绑定了用户属性的值取其中的 value
+fn value(value: &Value) -> &Value {
+    match value {
+        Value::Object(map) if map.contains_key("value") => &map["value"],
+        other => other,
+    }
+}
+
+fn string(v: Option<&Value>) -> Option<String> {
+    v.map(value).and_then(Value::as_str).map(str::to_string)
+}
+
+fn number(v: Option<&Value>, default: f32) -> f32 {
+    match v.map(value) {
+        Some(Value::Number(n)) => n.as_f64().map_or(default, |n| n as f32),
+        Some(Value::String(s)) => s.trim().parse().unwrap_or(default),
+        _ => default,
+    }
+}
+
+fn boolean(v: Option<&Value>, default: bool) -> bool {
+    match v.map(value) {
+        Some(Value::Bool(b)) => *b,
+        Some(Value::Number(n)) => n.as_f64().map_or(default, |n| n != 0.0),
+        Some(Value::String(s)) => match s.trim() {
+            "true" | "1" => true,
+            "false" | "0" => false,
+            _ => default,
+        },
+        _ => default,
+    }
+}
+
+// 向量可以写作 "x y z" 字符串、数组或单个数字
+fn components<const N: usize>(v: Option<&Value>, default: [f32; N]) -> [f32; N] {
+    let parts: Vec<f32> = match v.map(value) {
+        Some(Value::String(s)) => s
+            .split_whitespace()
+            .filter_map(|part| part.parse().ok())
+            .collect(),
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(|item| item.as_f64().map(|n| n as f32))
+            .collect(),
+        Some(Value::Number(n)) => n.as_f64().map_or_else(Vec::new, |n| vec![n as f32; N]),
+        _ => Vec::new(),
+    };
+    if parts.len() < N {
+        return default;
+    }
+    let mut result = default;
+    result.copy_from_slice(&parts[..N]);
+    result
+}
+
+fn vec2(v: Option<&Value>, default: [f32; 2]) -> [f32; 2] {
+    components(v, default)
+}
+
+fn vec3(v: Option<&Value>, default: [f32; 3]) -> [f32; 3] {
+    components(v, default)
+}
+
+fn textures(v: Option<&Value>) -> Vec<Option<String>> {
+    v.and_then(Value::as_array)
+        .map(|items| {
+            items
+                .iter()
+                .map(|item| item.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn area(bounds: [f32; 4]) -> f32 {
+    (bounds[2] - bounds[0]).max(0.0) * (bounds[3] - bounds[1]).max(0.0)
+}
+
+fn intersect(a: [f32; 4], b: [f32; 4]) -> [f32; 4] {
+    [
+        a[0].max(b[0]),
+        a[1].max(b[1]),
+        a[2].min(b[2]),
+        a[3].min(b[3]),
+    ]
+}
@@ -0,0 +1,412 @@
+use crate::pkg::Pkg;
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use walkdir::WalkDir;
+use zip::ZipArchive;
+
+// 压缩包中单个条目（含整个 pkg）读入内存的上限
+const MAX_ZIP_ENTRY_SIZE: u64 = 2 * 1024 * 1024 * 1024;
+
+// 壁纸来源：壁纸大目录（创意工坊目录）或单个壁纸目录、单个 pkg、
+// 单个壁纸的 zip，以及包含多个壁纸目录的备份压缩包
+pub trait WallpaperSource {
+    // 列出来源中的壁纸
+    fn wallpapers(&self) -> Vec<Wallpaper>;
+    // 打开壁纸中的各组文件：每个 pkg 一组，没有 pkg 时为壁纸目录下的散装文件
+    fn open(&self, wallpaper: &Wallpaper) -> Result<Vec<Package>, String>;
+}
+
+pub struct Wallpaper {
+    pub name: String,          // 壁纸目录名
+    pub title: Option<String>, // project.json 中的 title
+    pub path: String,          // 壁纸位置，压缩包中的壁纸为 压缩包路径!/目录
+    key: PathBuf,              // 来源内部定位：目录、pkg 路径或压缩包内的目录
+}
+
+pub struct PackageEntry {
+    pub name: String, // 条目路径，以 / 分隔
+    pub size: u64,
+}
+
+pub struct Package {
+    pub path: String,            // pkg 路径、压缩包中的 pkg 或散装文件所在目录
+    pub version: Option<String>, // pkg 版本，散装文件为 None
+    pub entries: Vec<PackageEntry>,
+    content: Content,
+}
+
+enum Content {
+    Pkg(Pkg),
+    Files(Vec<PathBuf>), // 与 entries 一一对应的文件路径
+    Zip(Arc<Mutex<ZipArchive<File>>>, Vec<usize>), // 与 entries 一一对应的压缩包条目序号
+}
+
+impl Package {
+    fn from_pkg(path: String, pkg: Pkg) -> Package {
+        let entries = pkg
+            .entries
+            .iter()
+            .map(|entry| PackageEntry {
+                name: entry.name.clone(),
+                size: entry.length as u64,
+            })
+            .collect();
+        Package {
+            path,
+            version: Some(pkg.magic.clone()),
+            entries,
+            content: Content::Pkg(pkg),
+        }
+    }
+
+    // 按需读取第 index 个条目的内容
+    pub fn read(&self, index: usize) -> Result<Cow<'_, [u8]>, String> {
+        let name = self
+            .entries
+            .get(index)
+            .map(|entry| entry.name.as_str())
+            .ok_or_else(|| format!("条目序号 {} 超出范围", index))?;
+        match &self.content {
+            Content::Pkg(pkg) => pkg.entry_data(&pkg.entries[index]),
+            Content::Files(files) => fs::read(&files[index])
+                .map(Cow::Owned)
+                .map_err(|e| format!("读取 {:?} 失败: {}", files[index], e)),
+            Content::Zip(archive, indices) => {
+                let mut archive = archive.lock().map_err(|_| format!("读取 {} 失败", name))?;
+                read_zip_entry(&mut archive, indices[index])
+                    .map(Cow::Owned)
+                    .map_err(|e| format!("读取 {} 失败: {}", name, e))
+            }
+        }
+    }
+}
+
+// 在壁纸的各组文件中按路径查找条目（不区分大小写），如 scene.json、materials/bg.json
+pub fn find_entry<'a>(packages: &'a [Package], name: &str) -> Option<(&'a Package, usize)> {
+    let name = name.replace('\\', "/");
+    packages.iter().find_map(|package| {
+        package
+            .entries
+            .iter()
+            .position(|entry| entry.name.eq_ignore_ascii_case(&name))
+            .map(|index| (package, index))
+    })
+}
+
+// 按路径选择来源：目录、.pkg 或 .zip
+pub fn open(path: &Path) -> Result<Box<dyn WallpaperSource>, String> {
+    if path.is_dir() {
+        return Ok(Box::new(FolderSource {
+            root: path.to_path_buf(),
+        }));
+    }
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "pkg" => Ok(Box::new(PkgSource {
+            path: path.to_path_buf(),
+        })),
+        "zip" => Ok(Box::new(ZipSource::open(path)?)),
+        _ => Err(format!(
+            "不支持的壁纸来源 {:?}，请选择目录、pkg 或 zip 文件",
+            path
+        )),
+    }
+}
+
+// 壁纸大目录或单个壁纸目录
+pub struct FolderSource {
+    root: PathBuf,
+}
+
+impl WallpaperSource for FolderSource {
+    fn wallpapers(&self) -> Vec<Wallpaper> {
+        find_wallpaper_dirs(&self.root)
+            .into_iter()
+            .map(|dir| Wallpaper {
+                name: dir_name(&dir),
+                title: read_title(&dir),
+                path: dir.to_string_lossy().into_owned(),
+                key: dir,
+            })
+            .collect()
+    }
+
+    fn open(&self, wallpaper: &Wallpaper) -> Result<Vec<Package>, String> {
+        let dir = &wallpaper.key;
+        let pkgs = pkg_files(dir);
+        if !pkgs.is_empty() {
+            return pkgs
+                .into_iter()
+                .map(|file| {
+                    let pkg = Pkg::open(&file)?;
+                    Ok(Package::from_pkg(file.to_string_lossy().into_owned(), pkg))
+                })
+                .collect();
+        }
+
+        // 未打包的壁纸：目录下的所有文件
+        let mut entries = Vec::new();
+        let mut files = Vec::new();
+        for entry in WalkDir::new(dir).sort_by_file_name().into_iter().flatten() {
+            let path = entry.path();
+            if !path.is_file() {
+                continue;
+            }
+            let Ok(relative) = path.strip_prefix(dir) else {
+                continue;
+            };
+            entries.push(PackageEntry {
+                name: relative
+                    .iter()
+                    .map(|part| part.to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/"),
+                size: entry.metadata().map(|m| m.len()).unwrap_or(0),
+            });
+            files.push(path.to_path_buf());
+        }
+        Ok(vec![Package {
+            path: dir.to_string_lossy().into_owned(),
+            version: None,
+            entries,
+            content: Content::Files(files),
+        }])
+    }
+}
+
+// 单个 pkg 文件，壁纸名称与标题取自所在目录
+pub struct PkgSource {
+    path: PathBuf,
+}
+
+impl WallpaperSource for PkgSource {
+    fn wallpapers(&self) -> Vec<Wallpaper> {
+        let dir = self.path.parent().unwrap_or_else(|| Path::new(""));
+        let name = match dir.file_name() {
+            Some(name) => name.to_string_lossy().into_owned(),
+            None => stem(&self.path),
+        };
+        vec![Wallpaper {
+            name,
+            title: read_title(dir),
+            path: self.path.to_string_lossy().into_owned(),
+            key: self.path.clone(),
+        }]
+    }
+
+    fn open(&self, wallpaper: &Wallpaper) -> Result<Vec<Package>, String> {
+        let pkg = Pkg::open(&wallpaper.key)?;
+        Ok(vec![Package::from_pkg(wallpaper.path.clone(), pkg)])
+    }
+}
+
+// zip 压缩包：直接包含 project.json 或 pkg 的目录各为一个壁纸，
+// 单个壁纸的压缩包通常在根目录，备份压缩包中则为多个壁纸目录
+pub struct ZipSource {
+    path: PathBuf,
+    archive: Arc<Mutex<ZipArchive<File>>>,
+    names: Vec<String>, // 各条目路径，与压缩包序号对应
+    sizes: Vec<u64>,    // 各条目解压后的大小
+}
+
+impl ZipSource {
+    pub fn open(path: &Path) -> Result<ZipSource, String> {
+        let file = File::open(path).map_err(|e| format!("读取 {:?} 失败: {}", path, e))?;
+        let mut archive =
+            ZipArchive::new(file).map_err(|e| format!("解析压缩包 {:?} 失败: {}", path, e))?;
+        let mut names = Vec::new();
+        let mut sizes = Vec::new();
+        for i in 0..archive.len() {
+            let entry = archive
+                .by_index_raw(i)
+                .map_err(|e| format!("解析压缩包 {:?} 失败: {}", path, e))?;
+            names.push(entry.name().to_string());
+            sizes.push(entry.size());
+        }
+        Ok(ZipSource {
+            path: path.to_path_buf(),
+            archive: Arc::new(Mutex::new(archive)),
+            names,
+            sizes,
+        })
+    }
+
+    // prefix 目录下（含子目录）的所有文件及其相对路径，prefix 为空时为整个压缩包
+    fn files_in(&self, prefix: &str) -> impl Iterator<Item = (usize, &str)> + '_ {
+        let prefix = prefix.to_string();
+        self.names.iter().enumerate().filter_map(move |(i, name)| {
+            let rest = name.strip_prefix(&prefix)?;
+            (!rest.is_empty() && !rest.ends_with('/')).then_some((i, rest))
+        })
+    }
+
+    fn read(&self, index: usize) -> Result<Vec<u8>, String> {
+        let mut archive = self
+            .archive
+            .lock()
+            .map_err(|_| format!("读取压缩包 {:?} 失败", self.path))?;
+        read_zip_entry(&mut archive, index)
+    }
+}
+
+impl WallpaperSource for ZipSource {
+    fn wallpapers(&self) -> Vec<Wallpaper> {
+        // 以 project.json 或 pkg 所在的目录作为壁纸目录
+        let mut prefixes = BTreeSet::new();
+        for name in &self.names {
+            let (dir, file) = match name.rfind('/') {
+                Some(pos) => (&name[..pos + 1], &name[pos + 1..]),
+                None => ("", name.as_str()),
+            };
+            if file == "project.json" || file.ends_with(".pkg") {
+                prefixes.insert(dir.to_string());
+            }
+        }
+
+        let mut result = Vec::new();
+        for prefix in prefixes {
+            let name = match prefix.trim_end_matches('/').rsplit('/').next() {
+                Some(name) if !name.is_empty() => name.to_string(),
+                _ => stem(&self.path),
+            };
+            let title = self
+                .files_in(&prefix)
+                .find(|(_, file)| *file == "project.json")
+                .and_then(|(i, _)| self.read(i).ok())
+                .and_then(|data| parse_title(&String::from_utf8_lossy(&data)));
+            result.push(Wallpaper {
+                name,
+                title,
+                path: format!("{}!/{}", self.path.display(), prefix),
+                key: PathBuf::from(&prefix),
+            });
+        }
+        result
+    }
+
+    fn open(&self, wallpaper: &Wallpaper) -> Result<Vec<Package>, String> {
+        let prefix = wallpaper.key.to_string_lossy().into_owned();
+        let pkgs: Vec<(usize, String)> = self
+            .files_in(&prefix)
+            .filter(|(_, file)| !file.contains('/') && file.ends_with(".pkg"))
+            .map(|(i, file)| (i, file.to_string()))
+            .collect();
+        if !pkgs.is_empty() {
+            // 压缩包中的 pkg 无法映射，读入内存后解析
+            return pkgs
+                .into_iter()
+                .map(|(i, file)| {
+                    let path = format!("{}{}", wallpaper.path, file);
+                    let pkg = Pkg::parse(self.read(i)?)
+                        .map_err(|e| format!("解析 {} 失败: {}", path, e))?;
+                    Ok(Package::from_pkg(path, pkg))
+                })
+                .collect();
+        }
+
+        let mut entries = Vec::new();
+        let mut indices = Vec::new();
+        for (i, file) in self.files_in(&prefix) {
+            entries.push(PackageEntry {
+                name: file.to_string(),
+                size: self.sizes[i],
+            });
+            indices.push(i);
+        }
+        Ok(vec![Package {
+            path: wallpaper.path.clone(),
+            version: None,
+            entries,
+            content: Content::Zip(self.archive.clone(), indices),
+        }])
+    }
+}
+
+// 读取压缩包条目：声明大小超过上限的条目直接拒绝，
+// 解压时最多读取声明大小再多一个字节，实际数据超出声明大小（压缩炸弹）时报错
+fn read_zip_entry(archive: &mut ZipArchive<File>, index: usize) -> Result<Vec<u8>, String> {
+    let mut file = archive.by_index(index).map_err(|e| e.to_string())?;
+    let name = file.name().to_string();
+    let declared = file.size();
+    if declared > MAX_ZIP_ENTRY_SIZE {
+        return Err(format!("压缩包条目 {} 过大（{} 字节）", name, declared));
+    }
+    let mut data = Vec::with_capacity(declared.min(64 * 1024 * 1024) as usize);
+    (&mut file)
+        .take(declared + 1)
+        .read_to_end(&mut data)
+        .map_err(|e| e.to_string())?;
+    if data.len() as u64 > declared {
+        return Err(format!(
+            "压缩包条目 {} 解压后超过声明的大小 {} 字节",
+            name, declared
+        ));
+    }
+    Ok(data)
+}
+
+// 查找壁纸目录：target 本身即壁纸目录，或其子目录为各个壁纸
+// 包含 pkg 或 project.json 的目录视为壁纸目录
+pub(crate) fn find_wallpaper_dirs(target: &Path) -> Vec<PathBuf> {
+    if is_wallpaper_dir(target) {
+        return vec![target.to_path_buf()];
+    }
+    let mut dirs = Vec::new();
+    if let Ok(entries) = fs::read_dir(target) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() && is_wallpaper_dir(&path) {
+                dirs.push(path);
+            }
+        }
+    }
+    dirs.sort();
+    dirs
+}
+
+fn is_wallpaper_dir(dir: &Path) -> bool {
+    !pkg_files(dir).is_empty() || dir.join("project.json").is_file()
+}
+
+pub(crate) fn pkg_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_file() && path.extension().is_some_and(|e| e == "pkg") {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+pub(crate) fn read_title(dir: &Path) -> Option<String> {
+    let content = fs::read_to_string(dir.join("project.json")).ok()?;
+    parse_title(&content)
+}
+
+fn parse_title(content: &str) -> Option<String> {
+    let json: serde_json::Value = serde_json::from_str(content).ok()?;
+    json.get("title")?.as_str().map(|s| s.to_string())
+}
+
+fn dir_name(dir: &Path) -> String {
+    dir.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "wallpaper".to_string())
+}
+
+fn stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "wallpaper".to_string())
+}
@@ -0,0 +1,872 @@
+use crate::alpha::{self, AlphaMode};
+use crate::anim::{self, AnimFormat};
+use crate::dxt::{self, DxtKind};
+use crate::error::ParseError;
+use crate::reader::Reader;
+use image::codecs::png::PngEncoder;
+use image::{ColorType, ImageEncoder, RgbaImage};
+use serde_json::json;
+use std::borrow::Cow;
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+// Wallpaper Engine 纹理格式（小端）：
+//   TEXV0005\0 TEXI0001\0
+//   int32 格式, int32 标志, int32 纹理宽, int32 纹理高, int32 图像宽, int32 图像高, uint32 未知
+//   TEXB000x\0, int32 图片数量, [TEXB0003+ 时 int32 内嵌图片格式], [TEXB0004 时 int32 是否为 mp4]
+//   每张图片：int32 mipmap 数量，随后为各级 mipmap
+const MAX_MAGIC_LEN: usize = 16;
+// TEXB0004 视频 mipmap 中条件 JSON 的长度上限
+const MAX_CONDITION_LEN: usize = 64 * 1024;
+
+// 支持矩阵：各段已知的版本，其余版本解析时报错并给出版本字符串
+pub const SUPPORTED_VERSIONS: &[&str] = &["TEXV0005"];
+pub const SUPPORTED_HEADER_VERSIONS: &[&str] = &["TEXI0001"];
+// TEXB0001 无 LZ4；TEXB0002 起 mipmap 带 LZ4 标志；TEXB0003 起带内嵌图片格式；
+// TEXB0004 另有 mp4 标志，mp4 时 mipmap 前多出三个参数与条件 JSON
+pub const SUPPORTED_CONTAINERS: &[&str] = &["TEXB0001", "TEXB0002", "TEXB0003", "TEXB0004"];
+// TEXS0001 帧坐标为整数，TEXS0002 为浮点数，TEXS0003 另记录画布尺寸
+pub const SUPPORTED_FRAME_VERSIONS: &[&str] = &["TEXS0001", "TEXS0002", "TEXS0003"];
+// 单个 mipmap 解压后的长度上限（8192x8192 RGBA），同时是解码单张图片的内存上限
+const MAX_DECOMPRESSED_SIZE: usize = 8192 * 8192 * 4;
+// 单边尺寸与各类数量上限，防止异常文件导致过量分配
+pub const MAX_DIMENSION: u32 = 16384;
+const MAX_IMAGES: u32 = 1024;
+const MAX_MIPMAPS: u32 = 32;
+pub const MAX_FRAMES: u32 = 4096;
+
+// 纹理标志位
+pub const FLAG_NO_INTERPOLATION: u32 = 1;
+pub const FLAG_CLAMP_UVS: u32 = 2;
+pub const FLAG_IS_GIF: u32 = 4;
+
+// FreeImage 格式，-1 表示 mipmap 中为原始像素数据，其余为内嵌的完整图片文件
+pub const IMAGE_FORMAT_UNKNOWN: i32 = -1;
+pub const IMAGE_FORMAT_BMP: i32 = 0;
+pub const IMAGE_FORMAT_JPEG: i32 = 2;
+pub const IMAGE_FORMAT_PNG: i32 = 13;
+pub const IMAGE_FORMAT_TARGA: i32 = 17;
+pub const IMAGE_FORMAT_GIF: i32 = 25;
+pub const IMAGE_FORMAT_WEBP: i32 = 35;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TexFormat {
+    Rgba8888,
+    Dxt5,
+    Dxt3,
+    Dxt1,
+    Rg88,
+    R8,
+    Unknown(i32),
+}
+
+impl TexFormat {
+    fn from_i32(value: i32) -> TexFormat {
+        match value {
+            0 => TexFormat::Rgba8888,
+            4 => TexFormat::Dxt5,
+            6 => TexFormat::Dxt3,
+            7 => TexFormat::Dxt1,
+            8 => TexFormat::Rg88,
+            9 => TexFormat::R8,
+            other => TexFormat::Unknown(other),
+        }
+    }
+
+    fn to_i32(self) -> i32 {
+        match self {
+            TexFormat::Rgba8888 => 0,
+            TexFormat::Dxt5 => 4,
+            TexFormat::Dxt3 => 6,
+            TexFormat::Dxt1 => 7,
+            TexFormat::Rg88 => 8,
+            TexFormat::R8 => 9,
+            TexFormat::Unknown(value) => value,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TexHeader {
+    pub format: TexFormat,
+    pub flags: u32,
+    pub texture_width: u32, // 纹理尺寸（通常补齐为 2 的幂）
+    pub texture_height: u32,
+    pub image_width: u32, // 实际图像尺寸
+    pub image_height: u32,
+    pub unknown: u32,
+}
+
+// mipmap 数据直接引用 tex 文件内容，解析时不复制
+#[derive(Debug, Clone)]
+pub struct TexMipmap<'a> {
+    pub width: u32,
+    pub height: u32,
+    pub lz4: bool,              // 数据是否经过 LZ4 压缩
+    pub decompressed_size: u32, // 解压后长度
+    pub data: &'a [u8],
+}
+
+impl TexMipmap<'_> {
+    // mipmap 原始数据，LZ4 压缩时解压并校验声明的长度
+    pub fn payload(&self) -> Result<Cow<'_, [u8]>, ParseError> {
+        if !self.lz4 {
+            return Ok(Cow::Borrowed(self.data));
+        }
+
+        let size = self.decompressed_size as usize;
+        if size == 0 || size > MAX_DECOMPRESSED_SIZE {
+            return Err(ParseError::TooLarge {
+                requested: size as u64,
+                limit: MAX_DECOMPRESSED_SIZE as u64,
+            });
+        }
+        let data = lz4_flex::block::decompress(self.data, size)
+            .map_err(|e| ParseError::Decode(format!("LZ4 解压失败: {}", e)))?;
+        if data.len() != size {
+            return Err(ParseError::Decode(format!(
+                "LZ4 解压长度不符：声明 {} 字节，实际 {} 字节",
+                size,
+                data.len()
+            )));
+        }
+        Ok(Cow::Owned(data))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TexImage<'a> {
+    pub mipmaps: Vec<TexMipmap<'a>>, // 从大到小
+}
+
+// 动图帧信息（TEXS 段），坐标与尺寸为精灵图中的像素位置
+#[derive(Debug, Clone)]
+pub struct TexFrame {
+    pub image_id: u32,  // 所在图片的序号
+    pub frametime: f32, // 帧时长（秒）
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub width_y: f32, // 旋转帧时使用
+    pub height_x: f32,
+    pub height: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct TexFrames {
+    pub magic: String, // TEXS000x
+    pub width: u32,    // 动图画布尺寸
+    pub height: u32,
+    pub frames: Vec<TexFrame>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Tex<'a> {
+    pub magic: String,        // TEXV000x
+    pub header_magic: String, // TEXI000x
+    pub header: TexHeader,
+    pub container: String, // TEXB000x
+    pub image_format: i32, // 内嵌图片的 FreeImage 格式
+    pub mp4: bool,         // TEXB0004 中的视频纹理，mipmap 为 mp4 文件
+    pub images: Vec<TexImage<'a>>,
+    pub frames: Option<TexFrames>, // 仅动图纹理存在
+}
+
+// 导出选项
+#[derive(Default, Debug, Clone, Copy)]
+pub struct ConvertOptions {
+    pub anim_format: AnimFormat, // 动图纹理的导出格式
+    pub keep_padding: bool,      // 保留纹理补齐区域，不裁剪到实际图像尺寸
+    pub mip_level: MipLevel,     // 导出的 mipmap 层级
+    pub alpha: AlphaMode,        // 透明通道处理方式
+}
+
+// “最接近尺寸”的默认像素，界面可调范围为 16–8192
+pub const DEFAULT_MIP_SIZE: u32 = 256;
+
+// 导出的 mipmap 层级
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MipLevel {
+    #[default]
+    Largest, // 仅最大一级
+    All,          // 全部层级
+    Closest(u32), // 长边最接近指定像素的一级
+}
+
+impl MipLevel {
+    // 与界面下拉框序号对应：0 最大; 1 全部; 2 最接近指定尺寸
+    pub fn from_status(status: usize, size: u32) -> MipLevel {
+        match status {
+            1 => MipLevel::All,
+            2 => MipLevel::Closest(size),
+            _ => MipLevel::Largest,
+        }
+    }
+}
+
+impl<'a> Tex<'a> {
+    pub fn parse(data: &'a [u8]) -> Result<Tex<'a>, ParseError> {
+        let mut reader = Reader::new(data);
+        let magic = reader.read_null_string(MAX_MAGIC_LEN)?;
+        check_version(&magic, "TEXV", "tex", SUPPORTED_VERSIONS)?;
+        let header_magic = reader.read_null_string(MAX_MAGIC_LEN)?;
+        check_version(&header_magic, "TEXI", "tex 头部", SUPPORTED_HEADER_VERSIONS)?;
+
+        let header = TexHeader {
+            format: TexFormat::from_i32(reader.read_i32()?),
+            flags: reader.read_u32()?,
+            texture_width: reader.read_u32()?,
+            texture_height: reader.read_u32()?,
+            image_width: reader.read_u32()?,
+            image_height: reader.read_u32()?,
+            unknown: reader.read_u32()?,
+        };
+        check_limit(header.texture_width, header.texture_height)?;
+        check_limit(header.image_width, header.image_height)?;
+
+        let container = reader.read_null_string(MAX_MAGIC_LEN)?;
+        check_version(&container, "TEXB", "tex 图片容器", SUPPORTED_CONTAINERS)?;
+        let image_count = check_count("图片", reader.read_u32()?, MAX_IMAGES)?;
+        let (image_format, mp4, version) = match container.as_str() {
+            "TEXB0001" => (IMAGE_FORMAT_UNKNOWN, false, 1),
+            "TEXB0002" => (IMAGE_FORMAT_UNKNOWN, false, 2),
+            "TEXB0003" => (reader.read_i32()?, false, 3),
+            _ => {
+                let image_format = reader.read_i32()?;
+                let mp4 = reader.read_u32()? == 1 && image_format == IMAGE_FORMAT_UNKNOWN;
+                (image_format, mp4, if mp4 { 4 } else { 3 })
+            }
+        };
+
+        let mut images = Vec::new();
+        for _ in 0..image_count {
+            let mipmap_count = check_count("mipmap ", reader.read_u32()?, MAX_MIPMAPS)?;
+            let mut mipmaps = Vec::new();
+            for _ in 0..mipmap_count {
+                mipmaps.push(read_mipmap(&mut reader, version)?);
+            }
+            images.push(TexImage { mipmaps });
+        }
+
+        let frames = if header.flags & FLAG_IS_GIF != 0 {
+            Some(read_frames(&mut reader)?)
+        } else {
+            None
+        };
+
+        Ok(Tex {
+            magic,
+            header_magic,
+            header,
+            container,
+            image_format,
+            mp4,
+            images,
+            frames,
+        })
+    }
+
+    pub fn is_gif(&self) -> bool {
+        self.header.flags & FLAG_IS_GIF != 0
+    }
+
+    pub fn is_embedded(&self) -> bool {
+        self.mp4 || self.image_format != IMAGE_FORMAT_UNKNOWN
+    }
+
+    // 内嵌图片对应的文件扩展名
+    pub fn embedded_extension(&self) -> Option<&'static str> {
+        if self.mp4 {
+            return Some("mp4");
+        }
+        match self.image_format {
+            IMAGE_FORMAT_BMP => Some("bmp"),
+            IMAGE_FORMAT_JPEG => Some("jpg"),
+            IMAGE_FORMAT_PNG => Some("png"),
+            IMAGE_FORMAT_TARGA => Some("tga"),
+            IMAGE_FORMAT_GIF => Some("gif"),
+            IMAGE_FORMAT_WEBP => Some("webp"),
+            _ => None,
+        }
+    }
+
+    // 第一张图片的最大一级 mipmap
+    pub fn first_mipmap(&self) -> Result<&TexMipmap<'a>, ParseError> {
+        self.images
+            .first()
+            .and_then(|image| image.mipmaps.first())
+            .ok_or(ParseError::NoImage)
+    }
+
+    pub fn decode(&self) -> Result<RgbaImage, ParseError> {
+        self.decode_mipmap(self.first_mipmap()?)
+    }
+
+    // 按选项挑选第一张图片中要导出的 mipmap 序号
+    pub fn select_mipmaps(&self, level: MipLevel) -> Vec<usize> {
+        let mipmaps = match self.images.first() {
+            Some(image) if !image.mipmaps.is_empty() => &image.mipmaps,
+            _ => return Vec::new(),
+        };
+        match level {
+            MipLevel::Largest => vec![0],
+            MipLevel::All => (0..mipmaps.len()).collect(),
+            MipLevel::Closest(size) => {
+                let closest = mipmaps
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, m)| m.width.max(m.height).abs_diff(size))
+                    .map(|(i, _)| i)
+                    .unwrap_or(0);
+                vec![closest]
+            }
+        }
+    }
+
+    // 裁掉纹理补齐到 2 的幂时多出的区域，按 mipmap 与纹理尺寸的比例换算实际图像大小
+    pub fn crop_padding(&self, image: RgbaImage) -> RgbaImage {
+        let header = &self.header;
+        if self.is_embedded()
+            || header.texture_width == 0
+            || header.texture_height == 0
+            || header.image_width == 0
+            || header.image_height == 0
+        {
+            return image;
+        }
+
+        let scale = |size: u32, real: u32, full: u32| -> u32 {
+            let scaled = (real as u64 * size as u64).div_ceil(full as u64) as u32;
+            scaled.clamp(1, size)
+        };
+        let width = scale(image.width(), header.image_width, header.texture_width);
+        let height = scale(image.height(), header.image_height, header.texture_height);
+        if width == image.width() && height == image.height() {
+            return image;
+        }
+        image::imageops::crop_imm(&image, 0, 0, width, height).to_image()
+    }
+
+    pub fn decode_mipmap(&self, mipmap: &TexMipmap<'_>) -> Result<RgbaImage, ParseError> {
+        let data = mipmap.payload()?;
+        if self.mp4 {
+            return Err(ParseError::Unsupported(
+                "视频纹理（mp4）无法解码为图片".to_string(),
+            ));
+        }
+        if self.is_embedded() {
+            return decode_embedded(&data)
+                .map_err(|e| {
+                    ParseError::Decode(format!(
+                        "解码内嵌图片（格式 {}）失败: {}",
+                        self.image_format, e
+                    ))
+                })
+                .map(|image| image.to_rgba8());
+        }
+
+        check_dimensions(mipmap.width, mipmap.height)?;
+        let width = mipmap.width as usize;
+        let height = mipmap.height as usize;
+        let rgba = match self.header.format {
+            TexFormat::Dxt1 => dxt::decompress(DxtKind::Dxt1, &data, width, height)?,
+            TexFormat::Dxt3 => dxt::decompress(DxtKind::Dxt3, &data, width, height)?,
+            TexFormat::Dxt5 => dxt::decompress(DxtKind::Dxt5, &data, width, height)?,
+            TexFormat::Rgba8888 | TexFormat::Rg88 | TexFormat::R8 => {
+                expand_raw(self.header.format, &data, width, height)?
+            }
+            other => {
+                return Err(ParseError::Unsupported(format!(
+                    "暂不支持纹理格式 {:?}",
+                    other
+                )))
+            }
+        };
+        RgbaImage::from_raw(mipmap.width, mipmap.height, rgba).ok_or(ParseError::BadDimensions {
+            width: mipmap.width,
+            height: mipmap.height,
+        })
+    }
+}
+
+// 内嵌图片交给 image 解码，同样限制尺寸与内存
+pub(crate) fn decode_embedded(data: &[u8]) -> image::ImageResult<image::DynamicImage> {
+    let mut reader = image::io::Reader::new(Cursor::new(data)).with_guessed_format()?;
+    reader.limits(image_limits());
+    reader.decode()
+}
+
+// 解码图片文件时的尺寸与内存上限，与 tex 纹理的上限一致
+fn image_limits() -> image::io::Limits {
+    let mut limits = image::io::Limits::default();
+    limits.max_image_width = Some(MAX_DIMENSION);
+    limits.max_image_height = Some(MAX_DIMENSION);
+    limits.max_alloc = Some(MAX_DECOMPRESSED_SIZE as u64);
+    limits
+}
+
+// 校验待解码图片的尺寸，返回 RGBA 像素所需字节数
+pub(crate) fn check_dimensions(width: u32, height: u32) -> Result<usize, ParseError> {
+    if width == 0 || height == 0 || width > MAX_DIMENSION || height > MAX_DIMENSION {
+        return Err(ParseError::BadDimensions { width, height });
+    }
+    let bytes = width as u64 * height as u64 * 4;
+    if bytes > MAX_DECOMPRESSED_SIZE as u64 {
+        return Err(ParseError::TooLarge {
+            requested: bytes,
+            limit: MAX_DECOMPRESSED_SIZE as u64,
+        });
+    }
+    Ok(bytes as usize)
+}
+
+// 头部记录的尺寸可以为 0，但不能超过上限
+fn check_limit(width: u32, height: u32) -> Result<(), ParseError> {
+    if width > MAX_DIMENSION || height > MAX_DIMENSION {
+        return Err(ParseError::BadDimensions { width, height });
+    }
+    Ok(())
+}
+
+// 段头需以 prefix 开头且在支持列表中，否则返回指明版本字符串的错误
+fn check_version(
+    magic: &str,
+    prefix: &'static str,
+    what: &'static str,
+    supported: &[&str],
+) -> Result<(), ParseError> {
+    if !magic.starts_with(prefix) {
+        return Err(ParseError::BadMagic {
+            expected: what,
+            found: magic.to_string(),
+        });
+    }
+    if !supported.contains(&magic) {
+        return Err(ParseError::UnsupportedVersion {
+            what,
+            version: magic.to_string(),
+        });
+    }
+    Ok(())
+}
+
+// 只读取文件开头的各段版本（TEXV/TEXI/TEXB），不解析图片数据，
+// 用于在报告中标明纹理版本，即使该版本不受支持
+pub fn detect_versions(data: &[u8]) -> Result<String, ParseError> {
+    let mut reader = Reader::new(data);
+    let magic = reader.read_null_string(MAX_MAGIC_LEN)?;
+    if !magic.starts_with("TEXV") {
+        return Err(ParseError::BadMagic {
+            expected: "tex",
+            found: magic,
+        });
+    }
+    let header_magic = reader.read_null_string(MAX_MAGIC_LEN)?;
+    // TEXI 段为 7 个 int32
+    reader.read_bytes(7 * 4)?;
+    let container = reader.read_null_string(MAX_MAGIC_LEN)?;
+    Ok(format!("{}/{}/{}", magic, header_magic, container))
+}
+
+fn check_count(what: &'static str, count: u32, max: u32) -> Result<u32, ParseError> {
+    if count > max {
+        return Err(ParseError::CountTooLarge {
+            what,
+            count: count as u64,
+            max: max as u64,
+        });
+    }
+    Ok(count)
+}
+
+// 未压缩格式每像素字节数
+fn raw_pixel_size(format: TexFormat) -> Option<usize> {
+    match format {
+        TexFormat::Rgba8888 => Some(4),
+        TexFormat::Rg88 => Some(2),
+        TexFormat::R8 => Some(1),
+        _ => None,
+    }
+}
+
+// 将未压缩像素展开为 RGBA：R8 作为灰度，RG88 作为灰度 + alpha（R 为灰度，G 为透明度，不作为第二个颜色通道）
+fn expand_raw(
+    format: TexFormat,
+    data: &[u8],
+    width: usize,
+    height: usize,
+) -> Result<Vec<u8>, ParseError> {
+    let pixel_size = raw_pixel_size(format)
+        .ok_or_else(|| ParseError::Unsupported(format!("{:?} 不是未压缩格式", format)))?;
+    let expected = width * height * pixel_size;
+    if data.len() < expected {
+        return Err(ParseError::DataTooShort {
+            what: format!("{:?} {}x{}", format, width, height),
+            expected,
+            actual: data.len(),
+        });
+    }
+
+    let data = &data[..expected];
+    let rgba = match format {
+        TexFormat::Rgba8888 => data.to_vec(),
+        TexFormat::Rg88 => data
+            .chunks_exact(2)
+            .flat_map(|p| [p[0], p[0], p[0], p[1]])
+            .collect(),
+        _ => data.iter().flat_map(|&l| [l, l, l, 255]).collect(),
+    };
+    Ok(rgba)
+}
+
+fn read_mipmap<'a>(reader: &mut Reader<'a>, version: u32) -> Result<TexMipmap<'a>, ParseError> {
+    if version >= 4 {
+        // 视频 mipmap 前的三个参数与条件 JSON，内容不影响解码
+        reader.read_u32()?;
+        reader.read_u32()?;
+        reader.read_null_string(MAX_CONDITION_LEN)?;
+        reader.read_u32()?;
+    }
+    let width = reader.read_u32()?;
+    let height = reader.read_u32()?;
+    check_limit(width, height)?;
+    let (lz4, decompressed_size) = if version >= 2 {
+        let lz4 = reader.read_u32()? == 1;
+        (lz4, reader.read_u32()?)
+    } else {
+        (false, 0)
+    };
+    let length = reader.read_u32()? as usize;
+    let data = reader.read_bytes(length)?;
+    Ok(TexMipmap {
+        width,
+        height,
+        lz4,
+        decompressed_size,
+        data,
+    })
+}
+
+fn read_frames(reader: &mut Reader) -> Result<TexFrames, ParseError> {
+    let magic = reader.read_null_string(MAX_MAGIC_LEN)?;
+    check_version(&magic, "TEXS", "tex 动图帧信息", SUPPORTED_FRAME_VERSIONS)?;
+    let count = check_count("帧", reader.read_u32()?, MAX_FRAMES)?;
+    let (mut width, mut height) = match magic.as_str() {
+        "TEXS0003" => (reader.read_u32()?, reader.read_u32()?),
+        _ => (0, 0),
+    };
+
+    let mut frames = Vec::new();
+    for _ in 0..count {
+        let image_id = reader.read_u32()?;
+        let frametime = reader.read_f32()?;
+        // TEXS0001 中坐标为整数，之后的版本为浮点数
+        let mut values = [0f32; 6];
+        for value in values.iter_mut() {
+            *value = if magic == "TEXS0001" {
+                reader.read_i32()? as f32
+            } else {
+                reader.read_f32()?
+            };
+        }
+        frames.push(TexFrame {
+            image_id,
+            frametime,
+            x: values[0],
+            y: values[1],
+            width: values[2],
+            width_y: values[3],
+            height_x: values[4],
+            height: values[5],
+        });
+    }
+
+    // 未记录画布尺寸时使用第一帧的尺寸
+    if width == 0 || height == 0 {
+        if let Some(first) = frames.first() {
+            width = first.width.abs() as u32;
+            height = first.height.abs() as u32;
+        }
+    }
+    check_limit(width, height)?;
+    Ok(TexFrames {
+        magic,
+        width,
+        height,
+        frames,
+    })
+}
+
+// 在内存中转换 tex 数据，返回 (文件名, 内容) 列表，文件名以 stem 为基础
+// 内嵌 png/jpg 等图片原样输出，动图按选项合成，其余解码后编码为 png
+pub fn convert(
+    data: &[u8],
+    stem: &str,
+    options: &ConvertOptions,
+) -> Result<Vec<(String, Vec<u8>)>, String> {
+    convert_tex(&Tex::parse(data)?, stem, options)
+}
+
+// 转换已解析的 tex，供需要先检查纹理（如分类）再转换的调用方使用
+pub fn convert_tex(
+    tex: &Tex,
+    stem: &str,
+    options: &ConvertOptions,
+) -> Result<Vec<(String, Vec<u8>)>, String> {
+    if tex.frames.is_some() && options.anim_format != AnimFormat::Sheet {
+        let mut frames = anim::frames(tex)?;
+        for frame in &mut frames {
+            alpha::apply(&mut frame.image, options.alpha);
+        }
+        if options.anim_format == AnimFormat::Frames {
+            return anim::sequence(tex, &frames, stem);
+        }
+        let name = format!("{}.{}", stem, options.anim_format.extension());
+        return Ok(vec![(name, anim::encode(&frames, options.anim_format)?)]);
+    }
+
+    let image = tex.images.first().ok_or(ParseError::NoImage)?;
+    let mut outputs = Vec::new();
+    for level in tex.select_mipmaps(options.mip_level) {
+        let mipmap = &image.mipmaps[level];
+        // 导出全部层级时，第 1 级起追加 _mip1、_mip2 … 后缀
+        let base = if level > 0 && options.mip_level == MipLevel::All {
+            format!("{}_mip{}", stem, level)
+        } else {
+            stem.to_string()
+        };
+
+        // 内嵌图片在保留透明通道（默认）时按原字节写出；选择了其他透明通道处理时，
+        // 内嵌 png 会解码处理后重新编码，jpg 等其他格式仍原样写出
+        if tex.is_embedded() {
+            let ext = tex
+                .embedded_extension()
+                .ok_or_else(|| format!("不支持的内嵌图片格式 {}", tex.image_format))?;
+            let name = format!("{}.{}", base, ext);
+            let payload = mipmap.payload()?;
+            let content = alpha::apply_to_file(&name, &payload, options.alpha)?.into_owned();
+            outputs.push((name, content));
+            continue;
+        }
+
+        let mut decoded = tex.decode_mipmap(mipmap)?;
+        if !options.keep_padding {
+            decoded = tex.crop_padding(decoded);
+        }
+        alpha::apply(&mut decoded, options.alpha);
+        let png = encode_png(&decoded).map_err(|e| format!("编码 {}.png 失败: {}", base, e))?;
+        outputs.push((format!("{}.png", base), png));
+    }
+    Ok(outputs)
+}
+
+// 无法转换的纹理原样导出：<stem>.raw 为最大一级 mipmap 的数据（LZ4 可解压时为解压后数据），
+// <stem>.raw.json 记录解析出的头部、各 mipmap 信息与错误；tex 本身无法解析时导出整个文件
+pub fn raw_dump(data: &[u8], stem: &str, error: &str) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let (payload, sidecar) = match Tex::parse(data) {
+        Ok(tex) => {
+            let mipmap = tex.first_mipmap().ok();
+            // LZ4 解压失败时保留压缩数据，由 decompressed 与 lz4_error 说明
+            let (payload, lz4_error) = match mipmap.map(|mipmap| (mipmap, mipmap.payload())) {
+                Some((_, Ok(payload))) => (payload.into_owned(), None),
+                Some((mipmap, Err(e))) => (mipmap.data.to_vec(), Some(e.to_string())),
+                None => (Vec::new(), None),
+            };
+            let header = &tex.header;
+            let images: Vec<_> = tex
+                .images
+                .iter()
+                .map(|image| {
+                    image
+                        .mipmaps
+                        .iter()
+                        .map(|mipmap| {
+                            json!({
+                                "width": mipmap.width,
+                                "height": mipmap.height,
+                                "lz4": mipmap.lz4,
+                                "decompressed_size": mipmap.decompressed_size,
+                                "size": mipmap.data.len(),
+                            })
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .collect();
+            let sidecar = json!({
+                "version": tex.magic,
+                "header_version": tex.header_magic,
+                "container": tex.container,
+                "format": header.format.to_i32(),
+                "format_name": format!("{:?}", header.format),
+                "flags": header.flags,
+                "texture_width": header.texture_width,
+                "texture_height": header.texture_height,
+                "image_width": header.image_width,
+                "image_height": header.image_height,
+                "image_format": tex.image_format,
+                "mp4": tex.mp4,
+                "frames": tex.frames.as_ref().map(|frames| frames.frames.len()),
+                "images": images,
+                "payload": if mipmap.is_some() { "mipmap" } else { "none" },
+                "decompressed": mipmap.is_some() && lz4_error.is_none(),
+                "lz4_error": lz4_error,
+                "error": error,
+            });
+            (payload, sidecar)
+        }
+        Err(e) => {
+            let sidecar = json!({
+                "version": detect_versions(data).ok(),
+                "payload": "file",
+                "size": data.len(),
+                "parse_error": e.to_string(),
+                "error": error,
+            });
+            (data.to_vec(), sidecar)
+        }
+    };
+    let text = serde_json::to_string_pretty(&sidecar).map_err(|e| e.to_string())?;
+    Ok(vec![
+        (format!("{}.raw", stem), payload),
+        (format!("{}.raw.json", stem), text.into_bytes()),
+    ])
+}
+
+// 将 tex 文件转换为同目录下的同名图片，返回输出路径
+pub fn convert_file(path: &Path, options: &ConvertOptions) -> Result<Vec<PathBuf>, String> {
+    let data = fs::read(path).map_err(|e| format!("读取 {:?} 失败: {}", path, e))?;
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let mut targets = Vec::new();
+    for (name, content) in convert(&data, &stem, options)? {
+        let target = path.with_file_name(name);
+        fs::write(&target, content).map_err(|e| format!("写入 {:?} 失败: {}", target, e))?;
+        targets.push(target);
+    }
+    Ok(targets)
+}
+
+pub(crate) fn encode_png(image: &RgbaImage) -> Result<Vec<u8>, String> {
+    let mut data = Vec::new();
+    PngEncoder::new(&mut data)
+        .write_image(
+            image.as_raw(),
+            image.width(),
+            image.height(),
+            ColorType::Rgba8,
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(data)
+}
+
+// 编码选项
+#[derive(Debug, Clone, Copy)]
+pub struct EncodeOptions {
+    pub format: TexFormat, // 仅支持 RGBA8888 与 DXT5
+    pub mipmaps: bool,     // 生成完整的 mipmap 链
+    pub lz4: bool,         // mipmap 数据使用 LZ4 压缩
+}
+
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        EncodeOptions {
+            format: TexFormat::Dxt5,
+            mipmaps: true,
+            lz4: false,
+        }
+    }
+}
+
+// 将图片编码为 tex：纹理尺寸补齐为 2 的幂，写出 TEXV0005 / TEXI0001 / TEXB0003
+pub fn encode(image: &RgbaImage, options: &EncodeOptions) -> Result<Vec<u8>, String> {
+    if options.format != TexFormat::Rgba8888 && options.format != TexFormat::Dxt5 {
+        return Err(format!("暂不支持编码为 {:?}", options.format));
+    }
+    let (image_width, image_height) = image.dimensions();
+    if image_width == 0 || image_height == 0 {
+        return Err("图片尺寸为空".to_string());
+    }
+    check_dimensions(image_width, image_height).map_err(String::from)?;
+    // 补齐后的纹理也必须能被解码器读回
+    let texture_width = image_width.next_power_of_two();
+    let texture_height = image_height.next_power_of_two();
+    check_dimensions(texture_width, texture_height).map_err(|e| {
+        format!(
+            "图片尺寸 {}x{} 补齐为 {}x{} 后超出上限: {}",
+            image_width, image_height, texture_width, texture_height, e
+        )
+    })?;
+    let mut level = RgbaImage::new(texture_width, texture_height);
+    image::imageops::replace(&mut level, image, 0, 0);
+
+    let mut out = Vec::new();
+    out.extend_from_slice(b"TEXV0005\0TEXI0001\0");
+    for value in [
+        options.format.to_i32() as u32,
+        0,
+        texture_width,
+        texture_height,
+        image_width,
+        image_height,
+        0,
+    ] {
+        out.extend_from_slice(&value.to_le_bytes());
+    }
+    out.extend_from_slice(b"TEXB0003\0");
+    out.extend_from_slice(&1u32.to_le_bytes());
+    out.extend_from_slice(&IMAGE_FORMAT_UNKNOWN.to_le_bytes());
+
+    // 逐级缩小一半直到 1x1
+    let mut levels = vec![level];
+    loop {
+        let last = levels.last().unwrap();
+        let (w, h) = last.dimensions();
+        if !options.mipmaps || (w == 1 && h == 1) {
+            break;
+        }
+        let next = image::imageops::resize(
+            last,
+            (w / 2).max(1),
+            (h / 2).max(1),
+            image::imageops::FilterType::Triangle,
+        );
+        levels.push(next);
+    }
+
+    out.extend_from_slice(&(levels.len() as u32).to_le_bytes());
+    for level in &levels {
+        let (w, h) = level.dimensions();
+        // 未压缩的数据直接借用像素与 raw，不额外复制
+        let raw: Cow<[u8]> = match options.format {
+            TexFormat::Dxt5 => {
+                Cow::Owned(dxt::compress_dxt5(level.as_raw(), w as usize, h as usize))
+            }
+            _ => Cow::Borrowed(level.as_raw()),
+        };
+        let (lz4, data): (u32, Cow<[u8]>) = if options.lz4 {
+            (1, Cow::Owned(lz4_flex::block::compress(&raw)))
+        } else {
+            (0, Cow::Borrowed(&raw))
+        };
+        for value in [w, h, lz4, raw.len() as u32, data.len() as u32] {
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+        out.extend_from_slice(&data);
+    }
+    Ok(out)
+}
+
+// 读取 png/jpg 并编码为 tex 文件
+pub fn encode_file(input: &Path, output: &Path, options: &EncodeOptions) -> Result<(), String> {
+    // 超大图片在解码前即报错，不会耗尽内存
+    let mut reader = image::io::Reader::open(input)
+        .and_then(|reader| reader.with_guessed_format())
+        .map_err(|e| format!("读取图片 {:?} 失败: {}", input, e))?;
+    reader.limits(image_limits());
+    let image = reader
+        .decode()
+        .map_err(|e| format!("读取图片 {:?} 失败: {}", input, e))?
+        .to_rgba8();
+    let data = encode(&image, options)?;
+    fs::write(output, data).map_err(|e| format!("写入 {:?} 失败: {}", output, e))
+}
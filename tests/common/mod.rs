@@ -0,0 +1,102 @@
+// 各测试文件共用的临时目录与壁纸构造，不是每个测试文件都用到全部函数
+#![allow(dead_code)]
+
+use image::RgbaImage;
+use repkg::pkg;
+use repkg::re::{self, ExtractResult, Param};
+use repkg::source::{self, Package};
+use repkg::tex::{self, EncodeOptions, TexFormat};
+use std::path::{Path, PathBuf};
+
+// 测试用临时目录，以测试名和进程号区分，离开作用域（包括断言失败）时删除
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> TempDir {
+        let path = std::env::temp_dir().join(format!("repkg-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+
+    pub fn join(&self, path: impl AsRef<Path>) -> PathBuf {
+        self.0.join(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+// 4x4 纯色 RGBA8888 纹理
+pub fn texture(color: [u8; 4]) -> Vec<u8> {
+    let image = RgbaImage::from_pixel(4, 4, image::Rgba(color));
+    let options = EncodeOptions {
+        format: TexFormat::Rgba8888,
+        mipmaps: false,
+        lz4: false,
+    };
+    tex::encode(&image, &options).unwrap()
+}
+
+// 场景壁纸的 pkg 条目：scene.json，以及每个图层 (名称, 颜色) 对应的
+// models/<名称>.json → materials/<名称>.json → materials/<名称>.tex
+pub fn scene_files(scene: &[u8], layers: &[(&str, [u8; 4])]) -> Vec<(String, Vec<u8>)> {
+    let mut files = vec![("scene.json".to_string(), scene.to_vec())];
+    for (name, color) in layers {
+        files.push((
+            format!("models/{}.json", name),
+            format!(r#"{{"material":"materials/{}.json"}}"#, name).into_bytes(),
+        ));
+        files.push((
+            format!("materials/{}.json", name),
+            format!(
+                r#"{{"passes":[{{"blending":"translucent","textures":["{}"]}}]}}"#,
+                name
+            )
+            .into_bytes(),
+        ));
+        files.push((format!("materials/{}.tex", name), texture(*color)));
+    }
+    files
+}
+
+// 在 dir 下写出壁纸目录 library/123：scene.pkg 以及可选的 project.json，返回壁纸目录
+pub fn write_wallpaper(
+    dir: &TempDir,
+    files: &[(String, Vec<u8>)],
+    project: Option<&str>,
+) -> PathBuf {
+    let wallpaper = dir.join("library").join("123");
+    std::fs::create_dir_all(&wallpaper).unwrap();
+    if let Some(project) = project {
+        std::fs::write(wallpaper.join("project.json"), project).unwrap();
+    }
+    std::fs::write(
+        wallpaper.join("scene.pkg"),
+        pkg::build("PKGV0019", files).unwrap(),
+    )
+    .unwrap();
+    wallpaper
+}
+
+// 把条目写成 dir 下的 scene.pkg，再通过壁纸来源打开
+pub fn open_packages(dir: &TempDir, files: &[(String, Vec<u8>)]) -> Vec<Package> {
+    let path = dir.join("scene.pkg");
+    std::fs::write(&path, pkg::build("PKGV0019", files).unwrap()).unwrap();
+    let source = source::open(&path).unwrap();
+    source.open(&source.wallpapers()[0]).unwrap()
+}
+
+// 以 param 中的选项提取 wallpaper 到 out（自动创建）
+pub fn extract(wallpaper: &Path, out: &Path, param: Param) -> ExtractResult {
+    std::fs::create_dir_all(out).unwrap();
+    re::extract(Param {
+        target: wallpaper.to_string_lossy().into_owned(),
+        saved: out.to_string_lossy().into_owned(),
+        ..param
+    })
+    .unwrap()
+}
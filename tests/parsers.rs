@@ -0,0 +1,951 @@
+// pkg / tex 解析器与壁纸来源的测试：任意输入都只能返回错误，不能 panic 或过量分配
+mod common;
+
+use common::TempDir;
+use image::RgbaImage;
+use proptest::prelude::*;
+use repkg::error::ParseError;
+use repkg::pkg::{self, Pkg};
+use repkg::source;
+use repkg::tex::{self, EncodeOptions, Tex, TexFormat};
+
+// 合法的 tex 头部，后面拼接任意数据用于覆盖更深的解析分支
+fn tex_prefix(container: &[u8], flags: u32) -> Vec<u8> {
+    let mut data = b"TEXV0005\0TEXI0001\0".to_vec();
+    for value in [4u32, flags, 16, 16, 16, 16, 0] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    data.extend_from_slice(container);
+    data
+}
+
+fn parse_and_decode(data: &[u8]) {
+    if let Ok(tex) = Tex::parse(data) {
+        for image in &tex.images {
+            for mipmap in &image.mipmaps {
+                if let Ok(decoded) = tex.decode_mipmap(mipmap) {
+                    let _ = tex.crop_padding(decoded);
+                }
+            }
+        }
+        let _ = tex.select_mipmaps(tex::MipLevel::All);
+    }
+}
+
+proptest! {
+    #[test]
+    fn pkg_parse_never_panics(data in proptest::collection::vec(any::<u8>(), 0..512)) {
+        if let Ok(pkg) = Pkg::parse(data) {
+            for entry in &pkg.entries {
+                let _ = pkg.entry_data(entry).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn pkg_parse_with_valid_magic_never_panics(
+        count in any::<u32>(),
+        rest in proptest::collection::vec(any::<u8>(), 0..512),
+    ) {
+        let mut data = 8u32.to_le_bytes().to_vec();
+        data.extend_from_slice(b"PKGV0001");
+        data.extend_from_slice(&count.to_le_bytes());
+        data.extend_from_slice(&rest);
+        if let Ok(pkg) = Pkg::parse(data) {
+            for entry in &pkg.entries {
+                prop_assert!(pkg.entry_data(entry).unwrap().len() == entry.length as usize);
+            }
+        }
+    }
+
+    #[test]
+    fn pkg_build_round_trip(
+        files in proptest::collection::vec(
+            ("[a-z]{1,8}(/[a-z]{1,8}){0,2}\\.[a-z]{3}", proptest::collection::vec(any::<u8>(), 0..64)),
+            1..8,
+        )
+    ) {
+        let data = pkg::build(pkg::DEFAULT_VERSION, &files).unwrap();
+        let pkg = Pkg::parse(data).unwrap();
+        prop_assert_eq!(pkg.entries.len(), files.len());
+        for (entry, (name, content)) in pkg.entries.iter().zip(&files) {
+            prop_assert_eq!(&entry.name, name);
+            prop_assert_eq!(&*pkg.entry_data(entry).unwrap(), content.as_slice());
+        }
+    }
+
+    #[test]
+    fn tex_parse_never_panics(data in proptest::collection::vec(any::<u8>(), 0..512)) {
+        parse_and_decode(&data);
+    }
+
+    #[test]
+    fn tex_containers_never_panic(
+        container in prop_oneof![
+            Just(&b"TEXB0001\0"[..]),
+            Just(&b"TEXB0002\0"[..]),
+            Just(&b"TEXB0003\0"[..]),
+            Just(&b"TEXB0004\0"[..]),
+        ],
+        flags in 0u32..8,
+        rest in proptest::collection::vec(any::<u8>(), 0..1024),
+    ) {
+        let mut data = tex_prefix(container, flags);
+        data.extend_from_slice(&rest);
+        parse_and_decode(&data);
+    }
+
+    #[test]
+    fn tex_encode_round_trip(width in 1u32..40, height in 1u32..40, lz4 in any::<bool>()) {
+        let image = RgbaImage::from_fn(width, height, |x, y| {
+            image::Rgba([x as u8 * 5, y as u8 * 5, (x + y) as u8, 255])
+        });
+        let options = EncodeOptions {
+            format: TexFormat::Rgba8888,
+            mipmaps: true,
+            lz4,
+        };
+        let data = tex::encode(&image, &options).unwrap();
+        let tex = Tex::parse(&data).unwrap();
+        let decoded = tex.crop_padding(tex.decode().unwrap());
+        prop_assert_eq!(decoded, image);
+    }
+}
+
+// 用单个 mipmap 构造 TEXB0001 纹理并解码，返回逐行排列的像素
+fn decode_single_mipmap(format: u32, width: u32, height: u32, mipmap: &[u8]) -> Vec<[u8; 4]> {
+    let mut data = b"TEXV0005\0TEXI0001\0".to_vec();
+    for value in [format, 0, width, height, width, height, 0] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    data.extend_from_slice(b"TEXB0001\0");
+    for value in [1u32, 1, width, height, mipmap.len() as u32] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    data.extend_from_slice(mipmap);
+    let image = Tex::parse(&data).unwrap().decode().unwrap();
+    image.pixels().map(|pixel| pixel.0).collect()
+}
+
+#[test]
+fn single_channel_formats_expand_to_rgba() {
+    // R8 为灰度，RG88 的 R 为灰度、G 为透明度
+    assert_eq!(
+        decode_single_mipmap(9, 2, 1, &[10, 200]),
+        vec![[10, 10, 10, 255], [200, 200, 200, 255]]
+    );
+    assert_eq!(
+        decode_single_mipmap(8, 2, 1, &[10, 20, 200, 0]),
+        vec![[10, 10, 10, 20], [200, 200, 200, 0]]
+    );
+}
+
+#[test]
+fn dxt_fixed_block_vectors() {
+    let red = 0xf800u16.to_le_bytes();
+    let blue = 0x001fu16.to_le_bytes();
+    // 第 i 个像素使用索引 i % 4
+    let indices = 0xe4e4_e4e4u32.to_le_bytes();
+
+    // DXT1 四色模式：c0 > c1，索引 2、3 为 2/3 与 1/3 插值
+    let block = [&red[..], &blue, &indices].concat();
+    let pixels = decode_single_mipmap(7, 4, 4, &block);
+    let palette = [
+        [255, 0, 0, 255],
+        [0, 0, 255, 255],
+        [170, 0, 85, 255],
+        [85, 0, 170, 255],
+    ];
+    for (i, pixel) in pixels.iter().enumerate() {
+        assert_eq!(*pixel, palette[i % 4], "DXT1 像素 {}", i);
+    }
+
+    // DXT1 三色模式：c0 <= c1，索引 2 为中点，索引 3 为全透明黑色
+    let block = [&blue[..], &red, &indices].concat();
+    let pixels = decode_single_mipmap(7, 4, 4, &block);
+    let palette = [
+        [0, 0, 255, 255],
+        [255, 0, 0, 255],
+        [127, 0, 127, 255],
+        [0, 0, 0, 0],
+    ];
+    for (i, pixel) in pixels.iter().enumerate() {
+        assert_eq!(*pixel, palette[i % 4], "DXT1 三色像素 {}", i);
+    }
+
+    // DXT3：每像素 4 位显式透明度；颜色块总是四色模式，即使 c0 <= c1
+    let alpha: Vec<u8> = (0..8u8).map(|k| (2 * k) | ((2 * k + 1) << 4)).collect();
+    let color = [
+        &0u16.to_le_bytes()[..],
+        &0xffffu16.to_le_bytes(),
+        &[0xff; 4],
+    ]
+    .concat();
+    let pixels = decode_single_mipmap(6, 4, 4, &[alpha, color].concat());
+    for (i, pixel) in pixels.iter().enumerate() {
+        assert_eq!(*pixel, [170, 170, 170, i as u8 * 17], "DXT3 像素 {}", i);
+    }
+
+    // DXT5：a0 > a1 时 8 级插值，a0 <= a1 时 6 级插值外加 0 与 255
+    let mut bits = 0u64;
+    for i in 0..16 {
+        bits |= (i % 8) << (3 * i);
+    }
+    let color = [&0x07e0u16.to_le_bytes()[..], &[0; 6]].concat();
+    for (endpoints, palette) in [
+        ([255u8, 0], [255u8, 0, 218, 182, 145, 109, 72, 36]),
+        ([0, 255], [0, 255, 51, 102, 153, 204, 0, 255]),
+    ] {
+        let block = [&endpoints[..], &bits.to_le_bytes()[..6], &color].concat();
+        let pixels = decode_single_mipmap(4, 4, 4, &block);
+        for (i, pixel) in pixels.iter().enumerate() {
+            assert_eq!(*pixel, [0, 255, 0, palette[i % 8]], "DXT5 像素 {}", i);
+        }
+    }
+}
+
+// 16x16、8x8、4x4 三级 mipmap 的 RGBA8888 纹理，每级填充不同灰度
+fn mipmapped_tex() -> Vec<u8> {
+    let mut data = b"TEXV0005\0TEXI0001\0".to_vec();
+    for value in [0u32, 0, 16, 16, 16, 16, 0] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    data.extend_from_slice(b"TEXB0001\0");
+    data.extend_from_slice(&1u32.to_le_bytes());
+    data.extend_from_slice(&3u32.to_le_bytes());
+    for (level, size) in [16u32, 8, 4].into_iter().enumerate() {
+        for value in [size, size, size * size * 4] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.resize(data.len() + (size * size * 4) as usize, level as u8 * 100);
+    }
+    data
+}
+
+#[test]
+fn mip_levels_are_selected_by_size() {
+    use repkg::tex::MipLevel;
+    let data = mipmapped_tex();
+    let tex = Tex::parse(&data).unwrap();
+    assert_eq!(tex.select_mipmaps(MipLevel::Largest), [0]);
+    assert_eq!(tex.select_mipmaps(MipLevel::All), [0, 1, 2]);
+    assert_eq!(tex.select_mipmaps(MipLevel::Closest(5)), [2]);
+    assert_eq!(tex.select_mipmaps(MipLevel::Closest(1000)), [0]);
+    // 与两级距离相同时取较大的一级
+    assert_eq!(tex.select_mipmaps(MipLevel::Closest(12)), [0]);
+    assert_eq!(tex.select_mipmaps(MipLevel::Closest(6)), [1]);
+    assert_eq!(
+        MipLevel::from_status(2, tex::DEFAULT_MIP_SIZE),
+        MipLevel::Closest(256)
+    );
+
+    // 只有导出全部层级时才追加 _mipN 后缀
+    let convert = |mip_level| {
+        let options = tex::ConvertOptions {
+            mip_level,
+            ..Default::default()
+        };
+        let outputs = tex::convert(&data, "bg", &options).unwrap();
+        outputs
+            .into_iter()
+            .map(|(name, content)| {
+                let image = image::load_from_memory(&content).unwrap().to_rgba8();
+                (name, image.width(), image.get_pixel(0, 0).0[0])
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        convert(MipLevel::All),
+        [
+            ("bg.png".to_string(), 16, 0),
+            ("bg_mip1.png".to_string(), 8, 100),
+            ("bg_mip2.png".to_string(), 4, 200),
+        ]
+    );
+    assert_eq!(
+        convert(MipLevel::Closest(6)),
+        [("bg.png".to_string(), 8, 100)]
+    );
+}
+
+#[test]
+fn tex_dxt5_round_trip_within_error_bound() {
+    // 颜色沿一条直线渐变，透明度单独渐变，DXT5 能较好地还原
+    for (width, height) in [(16, 16), (64, 32), (8, 4)] {
+        let image = RgbaImage::from_fn(width, height, |x, y| {
+            let v = ((x + y) * 255 / (width + height)) as u8;
+            image::Rgba([v, v / 2 + 64, 255 - v, (255 - y * 255 / height) as u8])
+        });
+        let options = EncodeOptions {
+            format: TexFormat::Dxt5,
+            mipmaps: true,
+            lz4: true,
+        };
+        let data = tex::encode(&image, &options).unwrap();
+        let tex = Tex::parse(&data).unwrap();
+        assert_eq!(tex.header.format, TexFormat::Dxt5);
+        let decoded = tex.crop_padding(tex.decode().unwrap());
+        assert_eq!(decoded.dimensions(), image.dimensions());
+        // 颜色为 565 端点间 4 级插值，透明度为 8 级插值，误差约为块内变化范围的 1/6 与 1/14
+        for (a, b) in decoded.pixels().zip(image.pixels()) {
+            for channel in 0..3 {
+                assert!(a[channel].abs_diff(b[channel]) <= 24, "{:?} {:?}", a, b);
+            }
+            assert!(a[3].abs_diff(b[3]) <= 12, "{:?} {:?}", a, b);
+        }
+    }
+}
+
+#[test]
+fn tex_encode_rejects_textures_padded_past_decoder_limits() {
+    let options = EncodeOptions::default();
+    // 8193x4097 补齐为 16384x8192，超出解码上限
+    let error = tex::encode(&RgbaImage::new(8193, 4097), &options).unwrap_err();
+    assert!(error.contains("16384x8192"), "{}", error);
+    assert!(tex::encode(&RgbaImage::new(16385, 1), &options).is_err());
+}
+
+#[test]
+fn tex_encode_file_rejects_huge_images_before_decoding() {
+    let dir = TempDir::new("encode-file");
+    let options = EncodeOptions::default();
+    let small = dir.join("small.png");
+    RgbaImage::new(3, 2).save(&small).unwrap();
+    tex::encode_file(&small, &dir.join("small.tex"), &options).unwrap();
+    assert!(dir.join("small.tex").is_file());
+
+    // 只有头部的 20000x20000 png，按上限在分配像素前报错
+    let huge = dir.join("huge.png");
+    {
+        let file = std::fs::File::create(&huge).unwrap();
+        let mut encoder = png::Encoder::new(file, 20000, 20000);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.write_header().unwrap();
+    }
+    let error = tex::encode_file(&huge, &dir.join("huge.tex"), &options).unwrap_err();
+    assert!(error.contains("limit"), "{}", error);
+    assert!(!dir.join("huge.tex").exists());
+}
+
+#[test]
+fn pkg_rejects_oversized_entry_count() {
+    let mut data = 8u32.to_le_bytes().to_vec();
+    data.extend_from_slice(b"PKGV0001");
+    data.extend_from_slice(&u32::MAX.to_le_bytes());
+    assert!(matches!(
+        Pkg::parse(data),
+        Err(ParseError::CountTooLarge { .. })
+    ));
+}
+
+#[test]
+fn pkg_rejects_entry_past_end() {
+    let mut data = pkg::build("PKGV0001", &[("a.txt".to_string(), vec![1, 2, 3])]).unwrap();
+    data.truncate(data.len() - 1);
+    assert!(matches!(
+        Pkg::parse(data),
+        Err(ParseError::OutOfBounds { .. })
+    ));
+}
+
+#[test]
+fn tex_rejects_huge_dimensions() {
+    let mut data = tex_prefix(b"TEXB0003\0", 0);
+    data.extend_from_slice(&1u32.to_le_bytes()); // 图片数量
+    data.extend_from_slice(&(-1i32).to_le_bytes()); // 内嵌格式
+    data.extend_from_slice(&1u32.to_le_bytes()); // mipmap 数量
+    for value in [u32::MAX, u32::MAX, 0, 0, 0] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    assert!(matches!(
+        Tex::parse(&data),
+        Err(ParseError::BadDimensions { .. })
+    ));
+}
+
+#[test]
+fn tex_rejects_oversized_lz4_size() {
+    let mut data = tex_prefix(b"TEXB0003\0", 0);
+    data.extend_from_slice(&1u32.to_le_bytes());
+    data.extend_from_slice(&(-1i32).to_le_bytes());
+    data.extend_from_slice(&1u32.to_le_bytes());
+    for value in [16u32, 16, 1, u32::MAX, 4] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    data.extend_from_slice(&[0; 4]);
+    let tex = Tex::parse(&data).unwrap();
+    assert!(matches!(tex.decode(), Err(ParseError::TooLarge { .. })));
+}
+
+#[test]
+fn tex_reports_truncated_data() {
+    let data = tex_prefix(b"TEXB0002\0", 0);
+    assert!(matches!(
+        Tex::parse(&data),
+        Err(ParseError::UnexpectedEof { .. })
+    ));
+}
+
+#[test]
+fn unsafe_entry_paths_are_rejected() {
+    for name in [
+        "../evil.txt",
+        "materials/../../evil.txt",
+        "/etc/passwd",
+        "\\windows\\system32\\evil.dll",
+        "\\\\server\\share\\evil.txt",
+        "C:\\evil.txt",
+        "C:evil.txt",
+        "materials\\..\\..\\evil.txt",
+        "file.txt:stream",
+        "",
+        "./",
+    ] {
+        assert_eq!(pkg::safe_entry_path(name), None, "{:?}", name);
+    }
+    assert_eq!(
+        pkg::safe_entry_path("materials//./bg.tex"),
+        Some(std::path::PathBuf::from("materials").join("bg.tex"))
+    );
+    assert_eq!(
+        pkg::safe_entry_path("materials\\bg.tex"),
+        Some(std::path::PathBuf::from("materials").join("bg.tex"))
+    );
+}
+
+proptest! {
+    #[test]
+    fn safe_entry_path_stays_relative(name in "[a-zA-Z./\\\\:]{0,24}") {
+        if let Some(path) = pkg::safe_entry_path(&name) {
+            prop_assert!(path.components().all(|c| matches!(c, std::path::Component::Normal(_))));
+        }
+    }
+}
+
+#[test]
+fn extract_skips_unsafe_entries() {
+    let files = vec![
+        ("../escape.png".to_string(), b"bad".to_vec()),
+        ("..\\escape.jpg".to_string(), b"bad".to_vec()),
+        ("materials/ok.png".to_string(), b"good".to_vec()),
+    ];
+    let dir = TempDir::new("traversal");
+    let wallpaper = common::write_wallpaper(&dir, &files, None);
+    let out = dir.join("out").join("saved");
+    let result = common::extract(&wallpaper, &out, Default::default());
+    assert_eq!(result.file_count, 1, "{:?}", result.errors);
+    assert_eq!(result.errors.len(), 2);
+    assert!(result.errors.iter().all(|e| e.contains("路径不安全")));
+    assert!(out.join("123").join("ok.png").is_file());
+    for folder in [dir.join("out"), out.clone(), out.join("123")] {
+        assert!(!folder.join("escape.png").exists());
+        assert!(!folder.join("escape.jpg").exists());
+    }
+}
+
+#[test]
+fn untrusted_titles_stay_inside_output_folder() {
+    let files = vec![("a.png".to_string(), b"png".to_vec())];
+    let dir = TempDir::new("title");
+    for (index, title) in ["..", ".", "...", " .. ", "a/../..", "bg. "]
+        .iter()
+        .enumerate()
+    {
+        let project = format!(r#"{{"title":{:?}}}"#, title);
+        let wallpaper = common::write_wallpaper(&dir, &files, Some(&project));
+        let out = dir.join(format!("out{}", index)).join("saved");
+        let result = common::extract(
+            &wallpaper,
+            &out,
+            repkg::re::Param {
+                as_title: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(result.file_count, 1, "{:?}: {:?}", title, result.errors);
+        assert!(!out.parent().unwrap().join("a.png").exists(), "{:?}", title);
+        let folders: Vec<_> = std::fs::read_dir(&out)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        assert_eq!(folders.len(), 1, "{:?}", title);
+        assert!(out.join(&folders[0]).join("a.png").is_file(), "{:?}", title);
+    }
+}
+
+// TEXB0003 内嵌图片纹理，mipmap 为完整的图片文件
+fn embedded_tex(image_format: i32, file: &[u8]) -> Vec<u8> {
+    let mut data = tex_prefix(b"TEXB0003\0", 0);
+    data.extend_from_slice(&1u32.to_le_bytes());
+    data.extend_from_slice(&image_format.to_le_bytes());
+    for value in [1u32, 2, 2, 0, 0, file.len() as u32] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    data.extend_from_slice(file);
+    data
+}
+
+#[test]
+fn embedded_images_are_written_verbatim() {
+    let image = RgbaImage::from_pixel(2, 2, image::Rgba([200, 100, 0, 128]));
+    // 带文本块的 png，重新编码后字节必然不同
+    let mut png_data = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut png_data, 2, 2);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .add_text_chunk("Comment".to_string(), "repkg".to_string())
+            .unwrap();
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(image.as_raw()).unwrap();
+    }
+    let mut jpeg_data = Vec::new();
+    let rgb = image::DynamicImage::ImageRgba8(image).to_rgb8();
+    image::codecs::jpeg::JpegEncoder::new(&mut jpeg_data)
+        .encode(rgb.as_raw(), 2, 2, image::ColorType::Rgb8)
+        .unwrap();
+    let files = vec![
+        (
+            "materials/photo.tex".to_string(),
+            embedded_tex(tex::IMAGE_FORMAT_PNG, &png_data),
+        ),
+        (
+            "materials/scan.tex".to_string(),
+            embedded_tex(tex::IMAGE_FORMAT_JPEG, &jpeg_data),
+        ),
+    ];
+    let dir = TempDir::new("embedded");
+    let wallpaper = common::write_wallpaper(&dir, &files, None);
+
+    let out = dir.join("keep");
+    let result = common::extract(&wallpaper, &out, Default::default());
+    assert_eq!(result.file_count, 2, "{:?}", result.errors);
+    assert_eq!(
+        std::fs::read(out.join("123").join("photo.png")).unwrap(),
+        png_data
+    );
+    assert_eq!(
+        std::fs::read(out.join("123").join("scan.jpg")).unwrap(),
+        jpeg_data
+    );
+
+    // 反预乘时内嵌 png 解码处理后重新编码，jpg 仍原样写出
+    let out = dir.join("unpremultiply");
+    let result = common::extract(
+        &wallpaper,
+        &out,
+        repkg::re::Param {
+            alpha_status: 2,
+            ..Default::default()
+        },
+    );
+    assert_eq!(result.file_count, 2, "{:?}", result.errors);
+    let photo = image::open(out.join("123").join("photo.png"))
+        .unwrap()
+        .to_rgba8();
+    assert_eq!(photo.get_pixel(0, 0).0, [255, 199, 0, 128]);
+    assert_eq!(
+        std::fs::read(out.join("123").join("scan.jpg")).unwrap(),
+        jpeg_data
+    );
+}
+
+#[test]
+fn unknown_versions_are_named_in_errors() {
+    let mut data = 8u32.to_le_bytes().to_vec();
+    data.extend_from_slice(b"PKGV9999");
+    data.extend_from_slice(&0u32.to_le_bytes());
+    let error = Pkg::parse(data).err().unwrap();
+    assert!(matches!(error, ParseError::UnsupportedVersion { .. }));
+    assert!(error.to_string().contains("PKGV9999"));
+
+    let data = tex_prefix(b"TEXB0009\0", 0);
+    let error = Tex::parse(&data).err().unwrap();
+    assert!(error.to_string().contains("TEXB0009"));
+    assert_eq!(
+        tex::detect_versions(&data).unwrap(),
+        "TEXV0005/TEXI0001/TEXB0009"
+    );
+}
+
+#[test]
+fn texb0004_video_is_embedded_mp4() {
+    let mut data = tex_prefix(b"TEXB0004\0", 0);
+    data.extend_from_slice(&1u32.to_le_bytes()); // 图片数量
+    data.extend_from_slice(&(-1i32).to_le_bytes()); // 内嵌格式
+    data.extend_from_slice(&1u32.to_le_bytes()); // mp4
+    data.extend_from_slice(&1u32.to_le_bytes()); // mipmap 数量
+    data.extend_from_slice(&1u32.to_le_bytes());
+    data.extend_from_slice(&2u32.to_le_bytes());
+    data.extend_from_slice(b"{}\0");
+    data.extend_from_slice(&1u32.to_le_bytes());
+    for value in [16u32, 16, 0, 0, 4] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    data.extend_from_slice(b"mp4!");
+    let tex = Tex::parse(&data).unwrap();
+    assert!(tex.mp4);
+    assert_eq!(tex.embedded_extension(), Some("mp4"));
+    assert_eq!(tex.first_mipmap().unwrap().data, b"mp4!");
+}
+
+#[test]
+fn mapped_and_buffered_reads_match() {
+    let files: Vec<(String, Vec<u8>)> = (0..2000)
+        .map(|i| {
+            let name = format!("materials/{:04}/{}.bin", i, "x".repeat(i % 200));
+            (name, vec![i as u8; i % 97])
+        })
+        .collect();
+    let data = pkg::build("PKGV0019", &files).unwrap();
+    // 条目表超过首次读入的 64KB，覆盖缓冲读取扩大头部的分支
+    assert!(data.len() > 128 * 1024);
+    let dir = TempDir::new("mmap");
+    let path = dir.join("scene.pkg");
+    std::fs::write(&path, &data).unwrap();
+
+    let mapped = Pkg::open(&path).unwrap();
+    let buffered = Pkg::open_buffered(&path).unwrap();
+    assert_eq!(mapped.magic, "PKGV0019");
+    assert_eq!(buffered.entries.len(), files.len());
+    for (entry, (name, content)) in buffered.entries.iter().zip(&files) {
+        assert_eq!(&entry.name, name);
+        assert_eq!(&*buffered.entry_data(entry).unwrap(), content.as_slice());
+        assert_eq!(&*mapped.entry_data(entry).unwrap(), content.as_slice());
+    }
+}
+
+#[test]
+fn pack_dir_skips_pkg_files_and_reuses_version() {
+    let dir = TempDir::new("pack");
+    let assets = dir.join("assets");
+    std::fs::create_dir_all(assets.join("materials").join("nested")).unwrap();
+    std::fs::write(assets.join("scene.json"), b"{}").unwrap();
+    std::fs::write(
+        assets.join("materials").join("nested").join("bg.tex"),
+        b"tex",
+    )
+    .unwrap();
+    std::fs::write(assets.join("backup.pkg"), b"not a pkg").unwrap();
+    let stale = vec![("old.json".to_string(), b"old".to_vec())];
+    std::fs::write(
+        assets.join("scene.pkg"),
+        pkg::build("PKGV0019", &stale).unwrap(),
+    )
+    .unwrap();
+
+    // 未指定版本时沿用已有 scene.pkg 的版本，已有的 pkg 不会被打包进去
+    let output = dir.join("out.pkg");
+    assert_eq!(pkg::pack_dir(&assets, &output, None).unwrap(), 2);
+    let packed = Pkg::open(&output).unwrap();
+    assert_eq!(packed.magic, "PKGV0019");
+    let names: Vec<&str> = packed.entries.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, ["materials/nested/bg.tex", "scene.json"]);
+    assert_eq!(&*packed.entry_data(&packed.entries[0]).unwrap(), b"tex");
+
+    // 输出到素材目录自身时也不会把自己打包进去
+    let output = assets.join("scene.pkg");
+    assert_eq!(
+        pkg::pack_dir(&assets, &output, Some("PKGV0022")).unwrap(),
+        2
+    );
+    assert_eq!(Pkg::open(&output).unwrap().magic, "PKGV0022");
+}
+
+#[test]
+fn zip_source_lists_pkg_and_loose_wallpapers() {
+    use std::io::Write;
+    let dir = TempDir::new("source");
+    let path = dir.join("backup.zip");
+    let pkg_data = pkg::build("PKGV0019", &[("scene.json".to_string(), b"{}".to_vec())]).unwrap();
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+    let options = zip::write::FileOptions::default();
+    for (name, data) in [
+        ("backup/1/project.json", &br#"{"title":"Packed"}"#[..]),
+        ("backup/1/scene.pkg", &pkg_data[..]),
+        ("backup/2/project.json", &b"{}"[..]),
+        ("backup/2/materials/a.png", &b"png"[..]),
+    ] {
+        zip.start_file(name, options).unwrap();
+        zip.write_all(data).unwrap();
+    }
+    zip.finish().unwrap();
+
+    let source = source::open(&path).unwrap();
+    let wallpapers = source.wallpapers();
+    assert_eq!(wallpapers.len(), 2);
+    assert_eq!(wallpapers[0].title.as_deref(), Some("Packed"));
+    let packed = source.open(&wallpapers[0]).unwrap();
+    assert_eq!(packed[0].version.as_deref(), Some("PKGV0019"));
+    assert_eq!(&*packed[0].read(0).unwrap(), b"{}");
+    let loose = source.open(&wallpapers[1]).unwrap();
+    assert_eq!(loose[0].version, None);
+    let index = loose[0]
+        .entries
+        .iter()
+        .position(|entry| entry.name == "materials/a.png")
+        .unwrap();
+    assert_eq!(&*loose[0].read(index).unwrap(), b"png");
+}
+
+#[test]
+fn zip_entries_larger_than_declared_are_rejected() {
+    use std::io::Write;
+    let dir = TempDir::new("bomb");
+    let path = dir.join("bomb.zip");
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let options =
+        zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    zip.start_file("1/scene.pkg", options).unwrap();
+    zip.write_all(&[0; 64 * 1024]).unwrap();
+    zip.start_file("1/project.json", options).unwrap();
+    zip.write_all(b"{}").unwrap();
+    let mut data = zip.finish().unwrap().into_inner();
+    // 把 scene.pkg 在本地头与中央目录中声明的解压大小改小
+    for (signature, offset) in [(b"PK\x03\x04", 22), (b"PK\x01\x02", 24)] {
+        let start = data
+            .windows(4)
+            .position(|window| window == signature)
+            .unwrap();
+        data[start + offset..start + offset + 4].copy_from_slice(&16u32.to_le_bytes());
+    }
+    std::fs::write(&path, data).unwrap();
+
+    let source = source::open(&path).unwrap();
+    let wallpapers = source.wallpapers();
+    let error = source.open(&wallpapers[0]).err().unwrap();
+    assert!(error.contains("超过声明的大小"), "{}", error);
+}
+
+#[test]
+fn unsupported_format_is_dumped_raw() {
+    let mut data = b"TEXV0005\0TEXI0001\0".to_vec();
+    for value in [12u32, 0, 4, 4, 4, 4, 0] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    data.extend_from_slice(b"TEXB0003\0");
+    data.extend_from_slice(&1u32.to_le_bytes());
+    data.extend_from_slice(&(-1i32).to_le_bytes());
+    data.extend_from_slice(&1u32.to_le_bytes());
+    for value in [4u32, 4, 0, 0, 8] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    data.extend_from_slice(b"rawbytes");
+
+    let options = tex::ConvertOptions::default();
+    let error = tex::convert(&data, "bg", &options).err().unwrap();
+    let outputs = tex::raw_dump(&data, "bg", &error).unwrap();
+    assert_eq!(outputs[0], ("bg.raw".to_string(), b"rawbytes".to_vec()));
+    assert_eq!(outputs[1].0, "bg.raw.json");
+    let sidecar: serde_json::Value = serde_json::from_slice(&outputs[1].1).unwrap();
+    assert_eq!(sidecar["format"], 12);
+    assert_eq!(sidecar["images"][0][0]["width"], 4);
+
+    assert_eq!(sidecar["decompressed"], true);
+    assert!(sidecar["lz4_error"].is_null());
+
+    let outputs = tex::raw_dump(b"broken", "bad", "error").unwrap();
+    assert_eq!(outputs[0].1, b"broken");
+}
+
+#[test]
+fn corrupt_lz4_mipmap_is_dumped_compressed() {
+    let mut data = tex_prefix(b"TEXB0003\0", 0);
+    data.extend_from_slice(&1u32.to_le_bytes());
+    data.extend_from_slice(&(-1i32).to_le_bytes());
+    data.extend_from_slice(&1u32.to_le_bytes());
+    // 声明 LZ4 压缩、解压后 1024 字节，数据却不是合法的 LZ4 块
+    for value in [16u32, 16, 1, 1024, 8] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    data.extend_from_slice(&[0xff; 8]);
+
+    let error = tex::convert(&data, "bg", &Default::default())
+        .err()
+        .unwrap();
+    let outputs = tex::raw_dump(&data, "bg", &error).unwrap();
+    assert_eq!(outputs[0], ("bg.raw".to_string(), vec![0xff; 8]));
+    let sidecar: serde_json::Value = serde_json::from_slice(&outputs[1].1).unwrap();
+    assert_eq!(sidecar["payload"], "mipmap");
+    assert_eq!(sidecar["decompressed"], false);
+    assert!(
+        sidecar["lz4_error"].as_str().unwrap().contains("LZ4"),
+        "{}",
+        sidecar
+    );
+}
+
+#[test]
+fn animations_round_trip_frames_and_delays() {
+    use image::AnimationDecoder;
+    use repkg::anim::{self, AnimFormat, AnimFrame};
+    let colors = [[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 128]];
+    let frames: Vec<AnimFrame> = colors
+        .iter()
+        .enumerate()
+        .map(|(index, &color)| AnimFrame {
+            image: RgbaImage::from_pixel(6, 4, image::Rgba(color)),
+            delay_ms: 40 * (index as u32 + 1),
+        })
+        .collect();
+    let delays = [40, 80, 120];
+
+    // GIF 的帧时长以 10 毫秒为单位，颜色经过调色板量化，只检查不透明帧
+    let data = anim::encode(&frames, AnimFormat::Gif).unwrap();
+    let decoded = image::codecs::gif::GifDecoder::new(&data[..])
+        .unwrap()
+        .into_frames()
+        .collect_frames()
+        .unwrap();
+    assert_eq!(decoded.len(), 3);
+    for (index, frame) in decoded.iter().enumerate() {
+        let (numer, denom) = frame.delay().numer_denom_ms();
+        assert_eq!(numer / denom, delays[index]);
+        assert_eq!(frame.buffer().dimensions(), (6, 4));
+    }
+    assert_eq!(decoded[0].buffer().get_pixel(0, 0).0, colors[0]);
+    assert_eq!(decoded[1].buffer().get_pixel(0, 0).0, colors[1]);
+
+    let data = anim::encode(&frames, AnimFormat::Apng).unwrap();
+    let mut reader = png::Decoder::new(&data[..]).read_info().unwrap();
+    assert_eq!(reader.info().animation_control().unwrap().num_frames, 3);
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    for (index, color) in colors.iter().enumerate() {
+        reader.next_frame(&mut buffer).unwrap();
+        let control = reader.info().frame_control().unwrap();
+        assert_eq!(
+            control.delay_num as u32 * 1000 / control.delay_den as u32,
+            delays[index]
+        );
+        assert_eq!(&buffer[..4], color);
+    }
+
+    let data = anim::encode(&frames, AnimFormat::WebP).unwrap();
+    let mut decoder = image_webp::WebPDecoder::new(std::io::Cursor::new(data)).unwrap();
+    assert!(decoder.is_animated());
+    assert_eq!(decoder.num_frames(), 3);
+    assert_eq!(decoder.dimensions(), (6, 4));
+    let mut buffer = vec![0; decoder.output_buffer_size().unwrap()];
+    for (index, color) in colors.iter().enumerate() {
+        assert_eq!(decoder.read_frame(&mut buffer).unwrap(), delays[index]);
+        assert_eq!(&buffer[..4], color);
+    }
+}
+
+#[test]
+fn frame_sequence_names_and_manifest() {
+    use repkg::anim;
+    // 4x2 的精灵图，左半为红色、右半为绿色，TEXS0003 记录两帧 2x2
+    let mut data = b"TEXV0005\0TEXI0001\0".to_vec();
+    for value in [0u32, tex::FLAG_IS_GIF, 4, 2, 4, 2, 0] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    data.extend_from_slice(b"TEXB0001\0");
+    for value in [1u32, 1, 4, 2, 32] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    for _ in 0..2 {
+        for color in [
+            [255, 0, 0, 255],
+            [255, 0, 0, 255],
+            [0, 255, 0, 255],
+            [0, 255, 0, 255],
+        ] {
+            data.extend_from_slice(&color);
+        }
+    }
+    data.extend_from_slice(b"TEXS0003\0");
+    for value in [2u32, 2, 2] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    for (x, frametime) in [(0f32, 0.05f32), (2.0, 0.1)] {
+        data.extend_from_slice(&0u32.to_le_bytes());
+        for value in [frametime, x, 0.0, 2.0, 0.0, 0.0, 2.0] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    let tex = Tex::parse(&data).unwrap();
+    let frames = anim::frames(&tex).unwrap();
+    let files = anim::sequence(&tex, &frames, "fire").unwrap();
+    let names: Vec<&str> = files.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["fire_000.png", "fire_001.png", "fire.frames.json"]);
+
+    for ((_, content), color) in files.iter().zip([[255, 0, 0, 255], [0, 255, 0, 255]]) {
+        let image = image::load_from_memory(content).unwrap().to_rgba8();
+        assert_eq!(image.dimensions(), (2, 2));
+        assert!(image.pixels().all(|pixel| pixel.0 == color));
+    }
+
+    let manifest: serde_json::Value = serde_json::from_slice(&files[2].1).unwrap();
+    assert_eq!(manifest["width"], 2);
+    assert_eq!(manifest["height"], 2);
+    assert_eq!(manifest["frames"][0]["file"], "fire_000.png");
+    assert_eq!(manifest["frames"][1]["file"], "fire_001.png");
+    assert_eq!(manifest["frames"][0]["duration_ms"], 50);
+    assert_eq!(manifest["frames"][1]["duration_ms"], 100);
+    assert_eq!(manifest["frames"][1]["x"], 2.0);
+}
+
+#[test]
+fn alpha_modes() {
+    use repkg::alpha::{self, AlphaMode};
+    let source = RgbaImage::from_pixel(1, 1, image::Rgba([100, 50, 0, 128]));
+
+    let mut image = source.clone();
+    alpha::apply(&mut image, AlphaMode::Keep);
+    assert_eq!(image, source);
+
+    let mut image = source.clone();
+    alpha::apply(&mut image, AlphaMode::Matte([255, 255, 255]));
+    assert_eq!(image.get_pixel(0, 0).0, [177, 152, 127, 255]);
+
+    let mut image = source.clone();
+    alpha::apply(&mut image, AlphaMode::Unpremultiply);
+    assert_eq!(image.get_pixel(0, 0).0, [199, 100, 0, 128]);
+}
+
+#[test]
+fn classifier_separates_artwork_from_auxiliary_textures() {
+    use repkg::classify::{Classifier, TextureRole};
+    let encode = |color: [u8; 4]| {
+        let image = RgbaImage::from_pixel(8, 8, image::Rgba(color));
+        let options = EncodeOptions {
+            format: TexFormat::Rgba8888,
+            mipmaps: true,
+            lz4: false,
+        };
+        tex::encode(&image, &options).unwrap()
+    };
+    let artwork_data = encode([200, 80, 40, 255]);
+    let normal_data = encode([128, 128, 255, 255]);
+    let artwork = Tex::parse(&artwork_data).unwrap();
+    let normal = Tex::parse(&normal_data).unwrap();
+
+    let mut classifier = Classifier::new();
+    classifier.add_json(
+        "materials/bg.json",
+        br#"{"passes":[{"shader":"genericimage2","textures":["bg","masks/bg_mask_opacity"]}]}"#,
+    );
+    classifier.add_json(
+        "materials/effects/shake.json",
+        br#"{"passes":[{"textures":[null,"util/clouds_256"]}]}"#,
+    );
+    let role = |name: &str, tex: &Tex| classifier.classify(name, tex).0;
+    assert_eq!(role("materials/bg.tex", &artwork), TextureRole::Artwork);
+    assert_eq!(
+        role("materials/masks/bg_mask_opacity.tex", &artwork),
+        TextureRole::Auxiliary
+    );
+    assert_eq!(
+        role("materials/util/clouds_256.tex", &artwork),
+        TextureRole::Auxiliary
+    );
+    assert_eq!(
+        role("materials/water_flowmap.tex", &artwork),
+        TextureRole::Auxiliary
+    );
+    assert_eq!(role("materials/rock.tex", &normal), TextureRole::Auxiliary);
+    assert_eq!(role("materials/flower.tex", &artwork), TextureRole::Artwork);
+}
@@ -0,0 +1,283 @@
+// scene.json 模型、主背景图导出、场景合成、引用关系与信息报告的测试
+mod common;
+
+use common::TempDir;
+use repkg::graph;
+use repkg::re::Param;
+use repkg::scene::{ObjectKind, Scene};
+
+#[test]
+fn scene_json_is_parsed_into_typed_model() {
+    let data = br#"{
+        "camera": {"center": "0 0 -1", "eye": "0 0 0", "up": "0 1 0"},
+        "general": {"clearcolor": "0.5 0.5 0.5", "orthogonalprojection": {"width": 1920, "height": 1080}},
+        "objects": [
+            {"id": 1, "name": "bg", "image": "models/bg.json", "origin": "960 540 0",
+             "scale": "1.5 1.5 1", "angles": "0 0 0", "size": "1920 1080",
+             "visible": {"user": "showbg", "value": false},
+             "effects": [{"file": "effects/shake/effect.json", "passes": [{"textures": [null, "masks/m"]}]}]},
+            {"id": 2, "name": "rain", "particle": "particles/rain.json", "parent": 1},
+            {"id": 3, "name": "music", "sound": ["sounds/a.mp3"]}
+        ],
+        "version": 3
+    }"#;
+    let scene = Scene::parse(data).unwrap();
+    assert_eq!(scene.version, Some(3));
+    assert_eq!(scene.canvas_size(), Some((1920.0, 1080.0)));
+    assert_eq!(scene.camera.center, [0.0, 0.0, -1.0]);
+    assert_eq!(scene.objects.len(), 3);
+
+    let bg = &scene.objects[0];
+    assert_eq!(bg.origin, [960.0, 540.0, 0.0]);
+    assert_eq!(bg.scale, [1.5, 1.5, 1.0]);
+    assert!(!bg.visible);
+    let layer = bg.image().unwrap();
+    assert_eq!(layer.model, "models/bg.json");
+    assert_eq!(layer.size, Some([1920.0, 1080.0]));
+    assert_eq!(bg.effects[0].textures[0][1].as_deref(), Some("masks/m"));
+
+    // 父对象隐藏时子对象也不可见
+    assert!(!scene.is_visible(&scene.objects[1]));
+    assert!(matches!(scene.objects[2].kind, ObjectKind::Sound { ref files } if files.len() == 1));
+    assert_eq!(scene.image_layers().count(), 1);
+}
+
+#[test]
+fn main_only_exports_background_named_after_title() {
+    let scene = br#"{
+        "general": {"orthogonalprojection": {"width": 1920, "height": 1080}},
+        "objects": [
+            {"id": 1, "image": "models/bg.json", "origin": "960 540 0", "size": "1920 1080"},
+            {"id": 2, "image": "models/fg.json", "origin": "300 300 0", "size": "2000 2000", "visible": false},
+            {"id": 3, "image": "models/fg.json", "origin": "100 100 0", "size": "200 200"}
+        ]
+    }"#;
+    let files = common::scene_files(
+        scene,
+        &[("bg", [10, 20, 30, 255]), ("fg", [200, 0, 0, 255])],
+    );
+    let dir = TempDir::new("main");
+    let wallpaper = common::write_wallpaper(&dir, &files, Some(r#"{"title":"Sunset"}"#));
+    let out = dir.join("out");
+
+    let result = common::extract(
+        &wallpaper,
+        &out,
+        Param {
+            main_only: true,
+            ..Default::default()
+        },
+    );
+    assert_eq!(result.file_count, 1, "{:?}", result.errors);
+    let image = image::open(out.join("123").join("Sunset.png"))
+        .unwrap()
+        .to_rgba8();
+    assert_eq!(image.get_pixel(0, 0).0, [10, 20, 30, 255]);
+    assert_eq!(
+        result.versions,
+        vec!["123: PKGV0019；TEXV0005/TEXI0001/TEXB0003 ×1".to_string()]
+    );
+}
+
+#[test]
+fn compositor_flattens_visible_layers() {
+    let scene = br#"{
+        "general": {"clearcolor": "0 0 0", "orthogonalprojection": {"width": 8, "height": 4}},
+        "objects": [
+            {"id": 1, "image": "models/bg.json", "origin": "4 2 0", "size": "8 4"},
+            {"id": 2, "image": "models/fg.json", "origin": "2 2 0", "size": "2 2", "alpha": 0.5},
+            {"id": 3, "image": "models/fg.json", "origin": "6 2 0", "size": "2 2", "visible": false}
+        ]
+    }"#;
+    let files = common::scene_files(scene, &[("bg", [255, 0, 0, 255]), ("fg", [0, 0, 255, 255])]);
+    let dir = TempDir::new("compose");
+    let packages = common::open_packages(&dir, &files);
+    let scene = Scene::load(&packages).unwrap().unwrap();
+    let image = repkg::compose::compose(&scene, &packages).unwrap();
+
+    assert_eq!(image.dimensions(), (8, 4));
+    assert_eq!(image.get_pixel(0, 0).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(2, 2).0, [128, 0, 128, 255]);
+    assert_eq!(image.get_pixel(6, 2).0, [255, 0, 0, 255]);
+}
+
+#[test]
+fn reference_graph_tracks_users_and_orphans() {
+    let scene = br#"{
+        "objects": [
+            {"id": 1, "name": "Background", "image": "models/bg.json",
+             "effects": [{"file": "effects/shake/effect.json", "passes": [{"textures": [null, "masks/shake_mask"]}]}]},
+            {"id": 2, "name": "Rain", "particle": "particles/rain.json"}
+        ]
+    }"#;
+    let mut files = common::scene_files(scene, &[("bg", [0, 0, 0, 255])]);
+    for (name, data) in [
+        (
+            "effects/shake/effect.json",
+            &br#"{"passes":[{"material":"materials/effects/shake.json"}]}"#[..],
+        ),
+        (
+            "materials/effects/shake.json",
+            br#"{"passes":[{"textures":[null,"masks/default","util/noise"]}]}"#,
+        ),
+        (
+            "particles/rain.json",
+            br#"{"material":"materials/particle/drop.json"}"#,
+        ),
+        (
+            "materials/particle/drop.json",
+            br#"{"passes":[{"textures":["particle/drop"]}]}"#,
+        ),
+        ("materials/masks/shake_mask.tex", &[0]),
+        ("materials/particle/drop.tex", &[0]),
+        ("materials/unused.tex", &[0]),
+    ] {
+        files.push((name.to_string(), data.to_vec()));
+    }
+    let dir = TempDir::new("graph");
+    let packages = common::open_packages(&dir, &files);
+    let scene = Scene::load(&packages).unwrap().unwrap();
+    let graph = graph::build(&scene, &packages);
+
+    let bg = graph.primary_user("materials/bg.tex").unwrap();
+    assert_eq!((bg.object, bg.main), (1, true));
+    // scene.json 中的 pass 覆盖了特效材质的遮罩
+    let mask = graph
+        .primary_user("materials/masks/shake_mask.tex")
+        .unwrap();
+    assert_eq!(
+        (mask.name.as_str(), mask.slot, mask.main),
+        ("Background", 1, false)
+    );
+    assert!(graph.primary_user("materials/masks/default.tex").is_none());
+    let noise = graph
+        .textures
+        .iter()
+        .find(|texture| texture.entry == "materials/util/noise.tex")
+        .unwrap();
+    assert!(!noise.exists);
+    assert_eq!(
+        graph
+            .primary_user("materials/particle/drop.tex")
+            .unwrap()
+            .name,
+        "Rain"
+    );
+    assert_eq!(graph.orphans, vec!["materials/unused.tex".to_string()]);
+    assert!(graph.is_orphan("materials/unused.tex"));
+}
+
+#[test]
+fn info_reports_packages_textures_and_graph() {
+    let scene = br#"{
+        "general": {"orthogonalprojection": {"width": 4, "height": 4}},
+        "objects": [{"id": 1, "name": "Background", "image": "models/bg.json", "size": "4 4"}]
+    }"#;
+    let mut files = common::scene_files(scene, &[("bg", [0, 0, 0, 255])]);
+    files.push((
+        "materials/unused.tex".to_string(),
+        common::texture([255, 255, 255, 255]),
+    ));
+    let dir = TempDir::new("info");
+    let wallpaper = common::write_wallpaper(&dir, &files, Some(r#"{"title":"Sunset"}"#));
+
+    let report = repkg::info::inspect(&wallpaper).unwrap();
+    assert_eq!(report.len(), 1);
+    let info = &report[0];
+    assert_eq!(info.title.as_deref(), Some("Sunset"));
+    assert_eq!(info.pkgs.len(), 1);
+    let pkg = &info.pkgs[0];
+    assert_eq!(pkg.version, "PKGV0019");
+    assert!(pkg.error.is_none());
+    assert_eq!(pkg.entries.len(), files.len());
+
+    let textures: Vec<_> = pkg
+        .entries
+        .iter()
+        .filter_map(|entry| Some((entry.name.as_str(), entry.texture.as_ref()?)))
+        .collect();
+    assert_eq!(textures.len(), 2);
+    for (name, texture) in textures {
+        assert_eq!(texture.format, "RGBA8888", "{}", name);
+        assert_eq!((texture.width, texture.height), (4, 4), "{}", name);
+        assert_eq!(texture.mipmaps, 1, "{}", name);
+        assert!(texture.error.is_none(), "{}", name);
+    }
+
+    assert_eq!(info.main_layer, Some(1));
+    let graph = info.graph.as_ref().unwrap();
+    assert_eq!(
+        graph.primary_user("materials/bg.tex").unwrap().name,
+        "Background"
+    );
+    assert_eq!(graph.orphans, vec!["materials/unused.tex".to_string()]);
+}
+
+#[test]
+fn textures_named_by_object_and_orphans_skipped() {
+    let scene = br#"{
+        "objects": [
+            {"id": 1, "name": "Background", "image": "models/bg.json",
+             "effects": [{"file": "effects/shake/effect.json", "passes": [{"textures": [null, "masks/shake_mask"]}]}]},
+            {"id": 2, "name": "Background", "image": "models/fg.json"}
+        ]
+    }"#;
+    let mut files = common::scene_files(
+        scene,
+        &[("bg", [10, 20, 30, 255]), ("fg", [200, 0, 0, 255])],
+    );
+    for (name, data) in [
+        (
+            "effects/shake/effect.json",
+            br#"{"passes":[{"material":"materials/effects/shake.json"}]}"#.to_vec(),
+        ),
+        (
+            "materials/effects/shake.json",
+            br#"{"passes":[{"textures":[null,"masks/shake_mask"]}]}"#.to_vec(),
+        ),
+        (
+            "materials/masks/shake_mask.tex",
+            common::texture([255, 255, 255, 255]),
+        ),
+        ("materials/unused.tex", common::texture([0, 255, 0, 255])),
+    ] {
+        files.push((name.to_string(), data));
+    }
+    let dir = TempDir::new("object-names");
+    let wallpaper = common::write_wallpaper(&dir, &files, None);
+    let out = dir.join("out");
+
+    let result = common::extract(
+        &wallpaper,
+        &out,
+        Param {
+            name_by_object: true,
+            skip_orphans: true,
+            ..Default::default()
+        },
+    );
+    assert!(result.errors.is_empty(), "{:?}", result.errors);
+    let mut names: Vec<String> = std::fs::read_dir(out.join("123"))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    assert_eq!(
+        names,
+        [
+            "Background.png",
+            "Background_2.png",
+            "Background_shake_mask.png"
+        ]
+    );
+    assert_eq!(result.file_count, 3);
+    let image = image::open(out.join("123").join("Background.png"))
+        .unwrap()
+        .to_rgba8();
+    assert_eq!(image.get_pixel(0, 0).0, [10, 20, 30, 255]);
+    assert!(
+        result.skipped.iter().any(|line| line.contains("unused")),
+        "{:?}",
+        result.skipped
+    );
+}